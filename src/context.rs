@@ -1,14 +1,27 @@
+use std::collections::{HashMap, HashSet};
+
 use egui_wgpu::renderer::ScreenDescriptor;
 use egui_winit::EventResponse;
 use wgpu::{SurfaceTexture, TextureFormat};
 use winit::{
-    dpi::PhysicalSize,
-    event::{Event, WindowEvent},
+    dpi::{PhysicalPosition, PhysicalSize},
+    error::ExternalError,
+    event::{ElementState, Event, TouchPhase, VirtualKeyCode, WindowEvent},
     event_loop::EventLoopWindowTarget,
-    window::Window,
+    window::{CursorGrabMode, CursorIcon, Window},
 };
 
-use crate::io::{keyboard::Keyboard, mouse::Mouse};
+#[cfg(feature = "gamepad")]
+use crate::io::gamepad::Gamepads;
+use crate::io::{keyboard::Keyboard, mouse::Mouse, touch::Touch};
+#[cfg(feature = "persistence")]
+use crate::persistence::{Storage, WindowGeometry, WINDOW_GEOMETRY_KEY};
+use crate::platform_output::{PlatformOutputFilter, PlatformOutputHandler};
+#[cfg(feature = "record")]
+use crate::record::InputRecorder;
+
+/// Decides which window events are passed to Egui, see `Context::set_gui_event_filter`
+pub type GuiEventFilter = Box<dyn FnMut(&WindowEvent) -> bool>;
 
 /// `Context` stores some useful things you might want to use in your app, including input from a Keyboard and Mouse,
 /// everything you need to render using Wgpu and an EguiManager for all your gui needs!
@@ -18,10 +31,33 @@ pub struct Context {
 
     pub mouse: Mouse,
     pub keyboard: Keyboard,
-    /// If true, Egui will not process new window events
+    pub touch: Touch,
+    #[cfg(feature = "gamepad")]
+    pub gamepads: Gamepads,
+    /// If true, Egui will not process new window events, except for resizes and scale factor and
+    /// theme changes
     pub block_gui_input: bool,
-    /// If true, Egui will not receive keyboard inputs for the tab key.
+    /// If true, Egui will not receive keyboard inputs for the tab key. This is a shorthand for
+    /// `block_gui_key(VirtualKeyCode::Tab)`.
     pub block_gui_tab_input: bool,
+    /// If true, `mouse`, `keyboard` and `touch` don't receive presses, scrolling and text input
+    /// that Egui captured, e.g. clicks on an Egui window. The release of a filtered press is
+    /// dropped too, other releases are passed on so keys and buttons can't get stuck.
+    pub filter_gui_captured_input: bool,
+
+    cursor_grab: CursorGrabMode,
+    cursor_visible: bool,
+    mouse_look: bool,
+    focused: bool,
+    event_captured: bool,
+    blocked_gui_keys: HashSet<VirtualKeyCode>,
+    /// Set when a blocked key was pressed, so the character it types is kept from Egui as well
+    drop_next_gui_char: bool,
+    gui_event_filter: Option<GuiEventFilter>,
+    #[cfg(feature = "record")]
+    recorder: Option<InputRecorder>,
+    #[cfg(feature = "persistence")]
+    window_geometry: WindowGeometry,
 }
 
 /// Convenience struct to manage the required state to use Egui
@@ -29,6 +65,73 @@ pub struct EguiManager {
     renderer: egui_wgpu::Renderer,
     state: egui_winit::State,
     pub ctx: egui::Context,
+    frame_size: Option<([u32; 2], f32)>,
+    render_targets: HashMap<egui::TextureId, RenderTarget>,
+    theme: GuiTheme,
+    fonts: egui::FontDefinitions,
+    zoom_factor: f32,
+    platform_output_filter: Option<PlatformOutputFilter>,
+    /// Texture changes of the frames ended since the last `paint`, so frames that are never
+    /// painted don't lose their uploads
+    textures_delta: egui::TexturesDelta,
+    #[cfg(feature = "persistence")]
+    storage: Storage,
+}
+
+/// How the gui is styled, see `EguiManager::set_theme`
+#[derive(Debug, Clone, PartialEq)]
+pub enum GuiTheme {
+    Dark,
+    Light,
+    /// Follow the OS dark mode preference, dark if the platform doesn't report one
+    System,
+    Custom(Box<egui::Visuals>),
+}
+
+/// Where a font is added to a font family, see `EguiManager::add_font`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontPriority {
+    /// Used before the family's other fonts
+    Primary,
+    /// Only used for glyphs the family's other fonts don't have, e.g. CJK or icon fonts
+    Fallback,
+}
+
+/// A texture created by `EguiManager::create_render_target`
+struct RenderTarget {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    filter: wgpu::FilterMode,
+    follows_window: bool,
+}
+
+/// A built gui frame that is ready to be painted, see `EguiManager::end_frame`. It's fine to
+/// drop a frame without painting it, e.g. when `needs_repaint` says nothing changed: its texture
+/// changes are kept by the `EguiManager` and applied by the next `paint`.
+pub struct EguiFrame {
+    shapes: Vec<egui::epaint::ClippedShape>,
+    size_in_pixels: [u32; 2],
+    pixels_per_point: f32,
+    /// How long until Egui wants to be repainted, zero if it wants to be repainted right away
+    /// (e.g. because of an animation or changed state)
+    pub repaint_after: std::time::Duration,
+}
+
+impl EguiFrame {
+    /// Returns if Egui wants to be repainted right away
+    pub fn needs_repaint(&self) -> bool {
+        self.repaint_after.is_zero()
+    }
+
+    /// Returns the size in pixels of the target this frame was laid out for
+    pub fn size_in_pixels(&self) -> [u32; 2] {
+        self.size_in_pixels
+    }
+
+    /// Returns the pixels per point this frame was laid out with
+    pub fn pixels_per_point(&self) -> f32 {
+        self.pixels_per_point
+    }
 }
 
 /// Convenience struct holding everything you need to get rendering with Wgpu
@@ -56,48 +159,227 @@ impl WgpuState {
 
 impl Context {
     pub fn new(wgpu_state: WgpuState, egui: EguiManager) -> Context {
+        let mut mouse = Mouse::new();
+        mouse.set_scale_factor(wgpu_state.window.scale_factor());
+        let focused = wgpu_state.window.has_focus();
+        #[cfg(feature = "persistence")]
+        let window_geometry = WindowGeometry::from_window(&wgpu_state.window);
+
         Context {
             wgpu_state,
             egui,
 
-            mouse: Mouse::new(),
+            mouse,
             keyboard: Keyboard::new(),
+            touch: Touch::new(),
+            #[cfg(feature = "gamepad")]
+            gamepads: Gamepads::new(),
             block_gui_input: false,
             block_gui_tab_input: false,
+            filter_gui_captured_input: false,
+
+            cursor_grab: CursorGrabMode::None,
+            cursor_visible: true,
+            mouse_look: false,
+            focused,
+            event_captured: false,
+            blocked_gui_keys: HashSet::new(),
+            drop_next_gui_char: false,
+            gui_event_filter: None,
+            #[cfg(feature = "record")]
+            recorder: None,
+            #[cfg(feature = "persistence")]
+            window_geometry,
         }
     }
 
     /// This function is automatically called in the application loop, you shouldn't need to call it yourself
     pub fn handle_event(&mut self, event: &Event<()>) {
-        self.keyboard.handle_event(event);
-        self.mouse.handle_event(event);
+        #[cfg(feature = "record")]
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = recorder.record_event(event) {
+                log::error!("Stopped recording input: {}", e);
+                self.recorder = None;
+            }
+        }
+
+        self.event_captured = self.handle_gui_event(event);
+        if !(self.filter_gui_captured_input && self.event_captured) {
+            self.keyboard.handle_event(event);
+            self.mouse.handle_event(event);
+            self.touch.handle_event_with_mouse(event, &mut self.mouse);
+        } else {
+            self.keyboard.handle_captured_event(event);
+            self.mouse.handle_captured_event(event);
+        }
 
-        if let winit::event::Event::WindowEvent {
+        if let Event::WindowEvent {
             window_id: _,
-            event,
+            event: WindowEvent::Focused(focused),
+        } = event
+        {
+            self.focused = *focused;
+            if self.mouse_look {
+                self.apply_mouse_look(*focused);
+            }
+        }
+
+        #[cfg(feature = "persistence")]
+        if let Event::WindowEvent {
+            window_id: _,
+            event: WindowEvent::Resized(_) | WindowEvent::Moved(_),
         } = event
         {
-            if let winit::event::WindowEvent::Resized(new_size) = event {
-                self.wgpu_state.resize(*new_size);
-                let _ = self.egui.on_event(event);
-            } else if !self.block_gui_input {
-                if self.block_gui_tab_input {
-                    if let winit::event::WindowEvent::KeyboardInput {
-                        input:
-                            winit::event::KeyboardInput {
-                                virtual_keycode: Some(winit::event::VirtualKeyCode::Tab),
-                                ..
-                            },
-                        ..
-                    } = event
-                    {
-                    } else {
-                        let _ = self.egui.on_event(event);
-                    }
-                } else {
-                    let _ = self.egui.on_event(event);
+            self.window_geometry.update(&self.wgpu_state.window);
+            self.egui
+                .storage()
+                .set(WINDOW_GEOMETRY_KEY, &self.window_geometry);
+        }
+    }
+
+    /// Passes the event on to Egui and returns if Egui captured it
+    fn handle_gui_event(&mut self, event: &Event<()>) -> bool {
+        let winit::event::Event::WindowEvent {
+            window_id: _,
+            event,
+        } = event
+        else {
+            return false;
+        };
+
+        let response = if let winit::event::WindowEvent::Resized(new_size) = event {
+            self.wgpu_state.resize(*new_size);
+            let size = self.wgpu_state.size;
+            self.egui
+                .resize_window_render_targets(&self.wgpu_state.device, [size.width, size.height]);
+            self.egui.on_event(event)
+        } else if matches!(
+            event,
+            WindowEvent::ThemeChanged(_) | WindowEvent::ScaleFactorChanged { .. }
+        ) {
+            // Like resizing, these keep the gui in sync with the window so they're never blocked
+            self.egui.on_event(event)
+        } else if self.block_gui_input || !self.gui_accepts(event) {
+            return false;
+        } else {
+            self.egui.on_event(event)
+        };
+
+        if !response.consumed {
+            return false;
+        }
+        match event {
+            WindowEvent::MouseInput { state, .. } => *state == ElementState::Pressed,
+            WindowEvent::MouseWheel { .. } => true,
+            WindowEvent::Touch(touch) => touch.phase == TouchPhase::Started,
+            // Egui always reports Tab as consumed, even when it isn't using the keyboard
+            WindowEvent::KeyboardInput { input, .. } => {
+                input.state == ElementState::Pressed && self.egui.wants_keyboard_input()
+            }
+            WindowEvent::ReceivedCharacter(_) | WindowEvent::Ime(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns if the event passes the blocked keys and the gui event filter
+    fn gui_accepts(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput { input, .. } => {
+                let blocked = input.virtual_keycode.is_some_and(|key| {
+                    self.blocked_gui_keys.contains(&key)
+                        || self.block_gui_tab_input && key == VirtualKeyCode::Tab
+                });
+                // The character typed by a key arrives right after its press
+                self.drop_next_gui_char = blocked && input.state == ElementState::Pressed;
+                if blocked {
+                    return false;
                 }
             }
+            WindowEvent::ReceivedCharacter(_) if self.drop_next_gui_char => {
+                self.drop_next_gui_char = false;
+                return false;
+            }
+            _ => {}
+        }
+        self.gui_event_filter
+            .as_mut()
+            .is_none_or(|filter| filter(event))
+    }
+
+    /// Stop Egui from receiving presses and releases of this key, so it can be used as a hotkey
+    /// (e.g. to toggle a console) without also being typed into or handled by the gui. The
+    /// `ReceivedCharacter` that directly follows a press of the key is dropped as well.
+    pub fn block_gui_key(&mut self, key: VirtualKeyCode) {
+        self.blocked_gui_keys.insert(key);
+    }
+
+    /// Let Egui receive this key again after `block_gui_key`
+    pub fn unblock_gui_key(&mut self, key: VirtualKeyCode) {
+        self.blocked_gui_keys.remove(&key);
+    }
+
+    /// Returns the keys blocked with `block_gui_key`
+    pub fn blocked_gui_keys(&self) -> impl Iterator<Item = VirtualKeyCode> + '_ {
+        self.blocked_gui_keys.iter().copied()
+    }
+
+    /// Set a filter that decides which window events are passed to Egui, it's called for every
+    /// event except resizes and should return false for events Egui shouldn't see. Applied after
+    /// `block_gui_input` and the blocked keys. Note that blocking only the press or only the release
+    /// of a key or button can confuse Egui.
+    pub fn set_gui_event_filter(&mut self, filter: impl FnMut(&WindowEvent) -> bool + 'static) {
+        self.gui_event_filter = Some(Box::new(filter));
+    }
+
+    /// Remove the filter set with `set_gui_event_filter`
+    pub fn clear_gui_event_filter(&mut self) {
+        self.gui_event_filter = None;
+    }
+
+    /// Returns if Egui captured the event that is currently being handled, use this in
+    /// `Application::handle_event` to ignore input meant for the gui
+    pub fn event_captured_by_ui(&self) -> bool {
+        self.event_captured
+    }
+
+    /// Returns if Egui currently wants mouse or keyboard input, e.g. because the cursor is over an
+    /// Egui window or a text field has focus. Use this in `Application::update` to skip gameplay input.
+    pub fn input_captured_by_ui(&self) -> bool {
+        self.egui.wants_pointer_input() || self.egui.wants_keyboard_input()
+    }
+
+    /// Start recording all keyboard, mouse, resize and focus events along with the frame timings to
+    /// a new file at `path`, which can later be replayed with `record::InputReplay`
+    #[cfg(feature = "record")]
+    pub fn start_recording(&mut self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        self.recorder = Some(InputRecorder::create(path)?);
+        Ok(())
+    }
+
+    /// Stop recording and flush the recording to disk, this is done automatically when the window
+    /// is closed
+    #[cfg(feature = "record")]
+    pub fn stop_recording(&mut self) {
+        if let Some(mut recorder) = self.recorder.take() {
+            if let Err(e) = recorder.flush() {
+                log::error!("Couldn't write input recording: {}", e);
+            }
+        }
+    }
+
+    /// Returns if input is currently being recorded
+    #[cfg(feature = "record")]
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    #[cfg(feature = "record")]
+    pub(crate) fn record_frame(&mut self, delta: f32) {
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = recorder.record_frame(delta) {
+                log::error!("Stopped recording input: {}", e);
+                self.recorder = None;
+            }
         }
     }
 
@@ -105,45 +387,443 @@ impl Context {
     //     ScreenDescriptor { size_in_pixels: , pixels_per_point: () }
     // }
 
-    // Attempts to restrict the mouse movement to inside the window
-    //
-    // # Errors:
-    // This function can fail for a number of reasons, a common one might be that the mouse is already grabbed by another application or the OS
-    // this does happen occasionally such as if the user grabs the title bar of the window to drag it around on many Linux machines
-    // so be a little careful on when you try to grab the mouse, such as when receiving focus.
-    // pub fn set_mouse_grabbed(&self, grabbed: bool) -> Result<(), ExternalError> {
-    //     let gl_win = self.dis.gl_window();
-    //     let win = gl_win.window();
-    //
-    //     win.set_cursor_grab(grabbed)
-    // }
+    /// Attempts to grab the cursor with the given mode, falling back to the other grab mode if the
+    /// platform doesn't support the requested one (e.g. `Locked` isn't available on Windows and
+    /// `Confined` isn't available on macOS). Returns the mode that was applied.
+    ///
+    /// # Errors:
+    /// This function can fail for a number of reasons, a common one might be that the mouse is already grabbed by another application or the OS
+    /// this does happen occasionally such as if the user grabs the title bar of the window to drag it around on many Linux machines
+    /// so be a little careful on when you try to grab the mouse, such as when receiving focus.
+    pub fn set_cursor_grab(
+        &mut self,
+        mode: CursorGrabMode,
+    ) -> Result<CursorGrabMode, ExternalError> {
+        let window = &self.wgpu_state.window;
+        let applied = match (window.set_cursor_grab(mode), mode) {
+            (Ok(()), _) => mode,
+            (Err(_), CursorGrabMode::Locked) => window
+                .set_cursor_grab(CursorGrabMode::Confined)
+                .map(|()| CursorGrabMode::Confined)?,
+            (Err(_), CursorGrabMode::Confined) => window
+                .set_cursor_grab(CursorGrabMode::Locked)
+                .map(|()| CursorGrabMode::Locked)?,
+            (Err(e), CursorGrabMode::None) => return Err(e),
+        };
+        self.cursor_grab = applied;
+        Ok(applied)
+    }
 
-    // Sets the mouse visible or invisible
-    // pub fn set_mouse_visible(&self, visible: bool) {
-    //     let gl_win = self.dis.gl_window();
-    //     let win = gl_win.window();
-    //
-    //     win.set_cursor_visible(visible);
-    // }
+    /// Returns the cursor grab mode that was last applied successfully
+    pub fn cursor_grab(&self) -> CursorGrabMode {
+        self.cursor_grab
+    }
+
+    /// Sets the mouse cursor visible or invisible while it's over the window. While mouse look has
+    /// the cursor grabbed it stays hidden, the visibility is applied once mouse look releases it.
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        if !(self.mouse_look && self.focused) {
+            self.wgpu_state.window.set_cursor_visible(visible);
+        }
+        self.cursor_visible = visible;
+    }
+
+    /// Returns if the cursor was last set visible
+    pub fn cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    /// Sets the cursor icon, note that Egui also sets the cursor icon whenever the icon it wants
+    /// changes, e.g. when hovering a text field
+    pub fn set_cursor_icon(&self, icon: CursorIcon) {
+        self.wgpu_state.window.set_cursor_icon(icon);
+    }
+
+    /// Moves the cursor to the given position inside the window in physical pixels. The move is not
+    /// reported as cursor movement by `Mouse::cursor_delta`.
+    pub fn set_cursor_position(&mut self, x: f64, y: f64) -> Result<(), ExternalError> {
+        self.wgpu_state
+            .window
+            .set_cursor_position(PhysicalPosition::new(x, y))?;
+        self.mouse.warp_to((x, y));
+        Ok(())
+    }
+
+    /// Enables or disables mouse look, where the cursor is hidden and grabbed (`Locked` if
+    /// possible, otherwise `Confined`) so `Mouse::raw_motion` can be used to rotate a camera.
+    /// The cursor is only grabbed while the window is focused, the grab is released when the
+    /// window loses focus and grabbed again when it regains focus. Disabling mouse look restores
+    /// the visibility last set with `set_cursor_visible`.
+    pub fn set_mouse_look(&mut self, enabled: bool) {
+        self.mouse_look = enabled;
+        self.apply_mouse_look(enabled && self.focused);
+    }
+
+    /// Returns if mouse look is enabled, the cursor is only actually grabbed while the window is focused
+    pub fn mouse_look(&self) -> bool {
+        self.mouse_look
+    }
+
+    fn apply_mouse_look(&mut self, grab: bool) {
+        if grab {
+            if let Err(e) = self.set_cursor_grab(CursorGrabMode::Locked) {
+                log::warn!("Couldn't grab the cursor for mouse look: {}", e);
+            }
+            self.wgpu_state.window.set_cursor_visible(false);
+        } else {
+            if let Err(e) = self.set_cursor_grab(CursorGrabMode::None) {
+                log::warn!("Couldn't release the cursor grab: {}", e);
+            }
+            self.wgpu_state
+                .window
+                .set_cursor_visible(self.cursor_visible);
+        }
+    }
 }
 
 impl EguiManager {
-    /// Setup everything required to render Egui
+    /// Setup everything required to render Egui. With the `persistence` feature the gui state
+    /// stored for the executable's name is loaded, not the one for `Application::app_id`. Apps
+    /// that build their own `Context` for `run_with_context` and override `app_id` have to use
+    /// `with_app_id` instead.
     pub fn new<T>(
         device: &wgpu::Device,
         texture_format: TextureFormat,
         event_loop: &EventLoopWindowTarget<T>,
     ) -> EguiManager {
+        #[cfg(feature = "persistence")]
+        return EguiManager::with_app_id(
+            device,
+            texture_format,
+            event_loop,
+            &crate::persistence::default_app_id(),
+        );
+
+        #[cfg(not(feature = "persistence"))]
         EguiManager {
             renderer: egui_wgpu::Renderer::new(device, texture_format, None, 1),
             state: egui_winit::State::new(event_loop),
             ctx: egui::Context::default(),
+            frame_size: None,
+            render_targets: HashMap::new(),
+            theme: GuiTheme::Dark,
+            fonts: egui::FontDefinitions::default(),
+            zoom_factor: 1.0,
+            platform_output_filter: None,
+            textures_delta: egui::TexturesDelta::default(),
         }
     }
 
+    /// Setup everything required to render Egui, loading the gui state stored for `app_id` from
+    /// the platform config directory
+    #[cfg(feature = "persistence")]
+    pub fn with_app_id<T>(
+        device: &wgpu::Device,
+        texture_format: TextureFormat,
+        event_loop: &EventLoopWindowTarget<T>,
+        app_id: &str,
+    ) -> EguiManager {
+        let (storage, memory) = Storage::load(crate::persistence::storage_path(app_id));
+        EguiManager::with_storage(device, texture_format, event_loop, storage, memory)
+    }
+
+    /// Setup everything required to render Egui with already loaded gui state, see `Storage::load`
+    #[cfg(feature = "persistence")]
+    pub fn with_storage<T>(
+        device: &wgpu::Device,
+        texture_format: TextureFormat,
+        event_loop: &EventLoopWindowTarget<T>,
+        storage: Storage,
+        memory: Option<egui::Memory>,
+    ) -> EguiManager {
+        let ctx = egui::Context::default();
+        if let Some(memory) = memory {
+            ctx.memory_mut(|m| *m = memory);
+        }
+
+        EguiManager {
+            renderer: egui_wgpu::Renderer::new(device, texture_format, None, 1),
+            state: egui_winit::State::new(event_loop),
+            ctx,
+            frame_size: None,
+            render_targets: HashMap::new(),
+            theme: GuiTheme::Dark,
+            fonts: egui::FontDefinitions::default(),
+            zoom_factor: 1.0,
+            platform_output_filter: None,
+            textures_delta: egui::TexturesDelta::default(),
+            storage,
+        }
+    }
+
+    /// Returns the values and settings persisted with the gui state
+    #[cfg(feature = "persistence")]
+    pub fn storage(&mut self) -> &mut Storage {
+        &mut self.storage
+    }
+
+    /// Save the gui state to disk now, this is done automatically when the window is closed and
+    /// every `Storage::auto_save_interval`
+    #[cfg(feature = "persistence")]
+    pub fn save(&mut self) -> std::io::Result<()> {
+        self.storage.save(&self.ctx)
+    }
+
     /// Update egui state
     pub fn on_event(&mut self, event: &WindowEvent<'_>) -> EventResponse {
-        self.state.on_event(&self.ctx, event)
+        if let WindowEvent::ThemeChanged(theme) = event {
+            if self.theme == GuiTheme::System {
+                self.apply_system_theme(Some(*theme));
+            }
+        }
+        let response = self.state.on_event(&self.ctx, event);
+        if let WindowEvent::ScaleFactorChanged { scale_factor, .. } = event {
+            self.state
+                .set_pixels_per_point(*scale_factor as f32 * self.zoom_factor);
+        }
+        response
+    }
+
+    /// Set how the gui is styled, `window` is used to look up the OS preference for
+    /// `GuiTheme::System`, which is followed when it changes
+    pub fn set_theme(&mut self, theme: GuiTheme, window: &Window) {
+        match &theme {
+            GuiTheme::Dark => self.ctx.set_visuals(egui::Visuals::dark()),
+            GuiTheme::Light => self.ctx.set_visuals(egui::Visuals::light()),
+            GuiTheme::System => self.apply_system_theme(window.theme()),
+            GuiTheme::Custom(visuals) => self.ctx.set_visuals((**visuals).clone()),
+        }
+        self.theme = theme;
+    }
+
+    /// Returns the theme set with `set_theme`
+    pub fn theme(&self) -> &GuiTheme {
+        &self.theme
+    }
+
+    fn apply_system_theme(&self, theme: Option<winit::window::Theme>) {
+        self.ctx.set_visuals(match theme {
+            Some(winit::window::Theme::Light) => egui::Visuals::light(),
+            Some(winit::window::Theme::Dark) | None => egui::Visuals::dark(),
+        });
+    }
+
+    /// Add a font from the contents of a TTF or OTF file to the given families, e.g.
+    /// `FontFamily::Proportional` with `FontPriority::Fallback` for a CJK font, or a
+    /// `FontFamily::Name` of your choosing for an icon font
+    pub fn add_font(
+        &mut self,
+        name: &str,
+        data: Vec<u8>,
+        families: &[egui::FontFamily],
+        priority: FontPriority,
+    ) {
+        self.fonts
+            .font_data
+            .insert(name.to_owned(), egui::FontData::from_owned(data));
+        for family in families {
+            let fonts = self.fonts.families.entry(family.clone()).or_default();
+            fonts.retain(|font| font != name);
+            match priority {
+                FontPriority::Primary => fonts.insert(0, name.to_owned()),
+                FontPriority::Fallback => fonts.push(name.to_owned()),
+            }
+        }
+        self.ctx.set_fonts(self.fonts.clone());
+    }
+
+    /// Load a font file and add it like `add_font`
+    pub fn load_font(
+        &mut self,
+        name: &str,
+        path: impl AsRef<std::path::Path>,
+        families: &[egui::FontFamily],
+        priority: FontPriority,
+    ) -> std::io::Result<()> {
+        let data = std::fs::read(path)?;
+        self.add_font(name, data, families, priority);
+        Ok(())
+    }
+
+    /// Replace all fonts, including Egui's default ones
+    pub fn set_fonts(&mut self, fonts: egui::FontDefinitions) {
+        self.fonts = fonts;
+        self.ctx.set_fonts(self.fonts.clone());
+    }
+
+    /// Returns the fonts in use
+    pub fn fonts(&self) -> &egui::FontDefinitions {
+        &self.fonts
+    }
+
+    /// Let `handler` intercept clipboard, url and cursor icon requests from the gui before they're
+    /// applied to the window, e.g. a `platform_output::RecordingHandler` in tests
+    pub fn set_platform_output_handler(&mut self, handler: impl PlatformOutputHandler + 'static) {
+        self.platform_output_filter = Some(PlatformOutputFilter::new(Box::new(handler)));
+    }
+
+    /// Remove the handler set with `set_platform_output_handler`
+    pub fn clear_platform_output_handler(&mut self) {
+        self.platform_output_filter = None;
+    }
+
+    /// Scale the whole gui by `zoom_factor` on top of the window's scale factor
+    pub fn set_zoom_factor(&mut self, zoom_factor: f32) {
+        self.zoom_factor = zoom_factor;
+    }
+
+    /// Returns the zoom factor set with `set_zoom_factor`
+    pub fn zoom_factor(&self) -> f32 {
+        self.zoom_factor
+    }
+
+    /// Returns the pixels per point the gui is drawn with in `window`, the window's scale factor
+    /// multiplied by the zoom factor
+    pub fn pixels_per_point(&self, window: &Window) -> f32 {
+        window.scale_factor() as f32 * self.zoom_factor
+    }
+
+    /// Returns if Egui wants mouse input, e.g. because the cursor is over an Egui window or a
+    /// widget is being dragged
+    pub fn wants_pointer_input(&self) -> bool {
+        self.ctx.wants_pointer_input()
+    }
+
+    /// Returns if Egui wants keyboard input, e.g. because a text field has focus
+    pub fn wants_keyboard_input(&self) -> bool {
+        self.ctx.wants_keyboard_input()
+    }
+
+    /// Register a texture so it can be shown in the gui with `egui::Image`, e.g. an offscreen
+    /// render target. The texture needs `TextureUsages::TEXTURE_BINDING` and a filterable format,
+    /// and has to be registered again with `update_native_texture` whenever it's recreated.
+    pub fn register_native_texture(
+        &mut self,
+        device: &wgpu::Device,
+        view: &wgpu::TextureView,
+        filter: wgpu::FilterMode,
+    ) -> egui::TextureId {
+        self.renderer.register_native_texture(device, view, filter)
+    }
+
+    /// Point an id returned by `register_native_texture` at a different texture, e.g. after the
+    /// texture was recreated with a new size
+    ///
+    /// # Panics:
+    /// If `id` isn't registered
+    pub fn update_native_texture(
+        &mut self,
+        device: &wgpu::Device,
+        view: &wgpu::TextureView,
+        filter: wgpu::FilterMode,
+        id: egui::TextureId,
+    ) {
+        self.renderer
+            .update_egui_texture_from_wgpu_texture(device, view, filter, id);
+    }
+
+    /// Unregister a texture registered with `register_native_texture`, or destroy a render target
+    /// created with `create_render_target`
+    pub fn free_texture(&mut self, id: egui::TextureId) {
+        self.renderer.free_texture(&id);
+        self.render_targets.remove(&id);
+    }
+
+    /// Create a texture that can be rendered to and shown in the gui, the texture is owned by the
+    /// `EguiManager` and can be accessed with `render_target`. If `size` is `None` the texture
+    /// has the size of the window and is recreated automatically whenever the window is resized,
+    /// otherwise it can be resized with `resize_render_target`. The id stays the same when the
+    /// texture is recreated.
+    pub fn create_render_target(
+        &mut self,
+        wgpu_state: &WgpuState,
+        size: Option<[u32; 2]>,
+        format: TextureFormat,
+        filter: wgpu::FilterMode,
+    ) -> egui::TextureId {
+        let follows_window = size.is_none();
+        let size = size.unwrap_or([wgpu_state.size.width, wgpu_state.size.height]);
+        let (texture, view) = create_render_target_texture(&wgpu_state.device, size, format);
+        let id = self
+            .renderer
+            .register_native_texture(&wgpu_state.device, &view, filter);
+        self.render_targets.insert(
+            id,
+            RenderTarget {
+                texture,
+                view,
+                filter,
+                follows_window,
+            },
+        );
+        id
+    }
+
+    /// Returns the view of a texture created with `create_render_target`, to use as a color
+    /// attachment
+    pub fn render_target(&self, id: egui::TextureId) -> Option<&wgpu::TextureView> {
+        self.render_targets.get(&id).map(|target| &target.view)
+    }
+
+    /// Returns the texture created with `create_render_target`
+    pub fn render_target_texture(&self, id: egui::TextureId) -> Option<&wgpu::Texture> {
+        self.render_targets.get(&id).map(|target| &target.texture)
+    }
+
+    /// Recreate a texture created with `create_render_target` with a new size, does nothing if
+    /// the size didn't change or `id` isn't a render target
+    pub fn resize_render_target(
+        &mut self,
+        device: &wgpu::Device,
+        id: egui::TextureId,
+        size: [u32; 2],
+    ) {
+        let Some(target) = self.render_targets.get_mut(&id) else {
+            return;
+        };
+        let current = target.texture.size();
+        if [current.width, current.height] == size {
+            return;
+        }
+        let (texture, view) = create_render_target_texture(device, size, target.texture.format());
+        self.renderer
+            .update_egui_texture_from_wgpu_texture(device, &view, target.filter, id);
+        target.texture = texture;
+        target.view = view;
+    }
+
+    pub(crate) fn resize_window_render_targets(&mut self, device: &wgpu::Device, size: [u32; 2]) {
+        let ids: Vec<_> = self
+            .render_targets
+            .iter()
+            .filter(|(_, target)| target.follows_window)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in ids {
+            self.resize_render_target(device, id, size);
+        }
+    }
+
+    /// Store a resource for paint callbacks created with `utils::paint_callback::paint_callback`,
+    /// there can be one resource per type. Returns the resource of the same type that was stored
+    /// before.
+    pub fn insert_paint_resource<T: Send + Sync + 'static>(&mut self, resource: T) -> Option<T> {
+        self.renderer.paint_callback_resources.insert(resource)
+    }
+
+    /// Returns the stored paint callback resource of type `T`
+    pub fn paint_resource<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.renderer.paint_callback_resources.get()
+    }
+
+    /// Returns the stored paint callback resource of type `T` mutably
+    pub fn paint_resource_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        self.renderer.paint_callback_resources.get_mut()
+    }
+
+    /// Remove the stored paint callback resource of type `T` and return it
+    pub fn remove_paint_resource<T: Send + Sync + 'static>(&mut self) -> Option<T> {
+        self.renderer.paint_callback_resources.remove()
     }
 
     /// Render the `run_ui` to the `output` texture using Egui.
@@ -153,45 +833,204 @@ impl EguiManager {
         output: &SurfaceTexture,
         run_ui: impl FnOnce(&egui::Context),
     ) {
-        let run_output = self
-            .ctx
-            .run(self.state.take_egui_input(&wgpu_state.window), run_ui);
-        self.state.handle_platform_output(
-            &wgpu_state.window,
-            &self.ctx,
-            run_output.platform_output,
-        );
-        let screen_descriptor = ScreenDescriptor {
-            size_in_pixels: [wgpu_state.config.width, wgpu_state.config.height],
-            pixels_per_point: wgpu_state.window.scale_factor() as f32,
-        };
-
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
+        let frame = self.run_ui(wgpu_state, run_ui);
 
-        let mut encoder =
-            wgpu_state
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Egui command encoder"),
-                });
+        let mut encoder = create_egui_encoder(&wgpu_state.device);
+        let user_cmd_bufs = self.paint(
+            &wgpu_state.device,
+            &wgpu_state.queue,
+            &mut encoder,
+            &view,
+            frame,
+        );
+        submit_egui_encoder(&wgpu_state.queue, encoder, user_cmd_bufs);
+    }
 
-        let clipped_primitives = self.ctx.tessellate(run_output.shapes);
+    /// Render the `run_ui` to any texture view using Egui, e.g. an offscreen target, a texture
+    /// used in a 3D scene or a headless screenshot test. `size_in_pixels` should be the size of
+    /// the view, the gui is laid out as if the screen was `size_in_pixels / pixels_per_point`
+    /// points large. The view's format has to be the format the `EguiManager` was created with.
+    ///
+    /// The gui only receives `raw_input` (no input if `None`), the window's input is left for the
+    /// next `begin_frame`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_to_view(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view: &wgpu::TextureView,
+        size_in_pixels: [u32; 2],
+        pixels_per_point: f32,
+        raw_input: Option<egui::RawInput>,
+        run_ui: impl FnOnce(&egui::Context),
+    ) {
+        let mut encoder = create_egui_encoder(device);
+        let user_cmd_bufs = self.render_to_encoder(
+            device,
+            queue,
+            &mut encoder,
+            view,
+            size_in_pixels,
+            pixels_per_point,
+            raw_input,
+            run_ui,
+        );
+        submit_egui_encoder(queue, encoder, user_cmd_bufs);
+    }
+
+    /// Record the Egui render pass for `run_ui` into `encoder` without submitting anything, so it
+    /// can be ordered with your own passes in a single submission. Returns the command buffers
+    /// from Egui's paint callbacks, submit them before the encoder:
+    /// ```ignore
+    /// let user_cmd_bufs = ctx.egui.render_to_encoder(device, queue, &mut encoder, &view, size, ppp, None, run_ui);
+    /// queue.submit(user_cmd_bufs.into_iter().chain(std::iter::once(encoder.finish())));
+    /// ```
+    /// See `render_to_view` for the meaning of the other arguments.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_to_encoder(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        size_in_pixels: [u32; 2],
+        pixels_per_point: f32,
+        raw_input: Option<egui::RawInput>,
+        run_ui: impl FnOnce(&egui::Context),
+    ) -> Vec<wgpu::CommandBuffer> {
+        self.begin_frame_sized(size_in_pixels, pixels_per_point, raw_input);
+        run_ui(&self.ctx);
+        let frame = self.end_frame_sized();
+        self.paint(device, queue, encoder, view, frame)
+    }
+
+    /// Start building a gui frame for the whole window, build the gui with `ctx` afterwards and
+    /// finish with `end_frame`. This allows building the gui in `Application::update` while
+    /// handling input, and painting it later with `paint`.
+    pub fn begin_frame(&mut self, wgpu_state: &WgpuState) {
+        let size_in_pixels = [wgpu_state.config.width, wgpu_state.config.height];
+        let pixels_per_point = self.pixels_per_point(&wgpu_state.window);
+        // Pointer positions are converted to points with this
+        self.state.set_pixels_per_point(pixels_per_point);
+        let raw_input = self.state.take_egui_input(&wgpu_state.window);
+        self.start_frame(raw_input, size_in_pixels, pixels_per_point);
+    }
+
+    /// Like `begin_frame`, but for a gui that isn't shown in the window and will be painted to a
+    /// target of `size_in_pixels`, see `render_to_view`. Finish it with `end_frame_sized`.
+    pub fn begin_frame_sized(
+        &mut self,
+        size_in_pixels: [u32; 2],
+        pixels_per_point: f32,
+        raw_input: Option<egui::RawInput>,
+    ) {
+        self.start_frame(
+            raw_input.unwrap_or_default(),
+            size_in_pixels,
+            pixels_per_point,
+        );
+    }
+
+    fn start_frame(
+        &mut self,
+        mut raw_input: egui::RawInput,
+        size_in_pixels: [u32; 2],
+        pixels_per_point: f32,
+    ) {
+        raw_input.pixels_per_point = Some(pixels_per_point);
+        raw_input.screen_rect = Some(egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::vec2(size_in_pixels[0] as f32, size_in_pixels[1] as f32) / pixels_per_point,
+        ));
+        self.ctx.begin_frame(raw_input);
+        self.frame_size = Some((size_in_pixels, pixels_per_point));
+    }
+
+    /// Finish the gui frame started with `begin_frame`, applying its platform output (cursor icon,
+    /// clipboard, opened urls etc.) to the window. The returned frame can be painted with `paint`.
+    ///
+    /// # Panics:
+    /// If no frame was started with `begin_frame` or `begin_frame_sized`
+    pub fn end_frame(&mut self, wgpu_state: &WgpuState) -> EguiFrame {
+        let (frame, output) = self.finish_frame();
+        let platform_output = match &mut self.platform_output_filter {
+            Some(filter) => filter.filter(output),
+            None => output,
+        };
+        self.state
+            .handle_platform_output(&wgpu_state.window, &self.ctx, platform_output);
+        frame
+    }
+
+    /// Finish the gui frame started with `begin_frame_sized` without touching the window, its
+    /// platform output is discarded. The returned frame can be painted with `paint`.
+    ///
+    /// # Panics:
+    /// If no frame was started with `begin_frame` or `begin_frame_sized`
+    pub fn end_frame_sized(&mut self) -> EguiFrame {
+        self.finish_frame().0
+    }
+
+    fn finish_frame(&mut self) -> (EguiFrame, egui::PlatformOutput) {
+        let (size_in_pixels, pixels_per_point) = self
+            .frame_size
+            .take()
+            .expect("EguiManager::end_frame called without begin_frame");
+        let output = self.ctx.end_frame();
+        self.textures_delta.append(output.textures_delta);
+        #[cfg(feature = "persistence")]
+        self.storage.auto_save(&self.ctx);
+
+        let frame = EguiFrame {
+            shapes: output.shapes,
+            size_in_pixels,
+            pixels_per_point,
+            repaint_after: output.repaint_after,
+        };
+        (frame, output.platform_output)
+    }
+
+    /// Builds a whole gui frame with `run_ui`, shorthand for `begin_frame`, `run_ui` and `end_frame`
+    pub fn run_ui(
+        &mut self,
+        wgpu_state: &WgpuState,
+        run_ui: impl FnOnce(&egui::Context),
+    ) -> EguiFrame {
+        self.begin_frame(wgpu_state);
+        run_ui(&self.ctx);
+        self.end_frame(wgpu_state)
+    }
+
+    /// Record the render pass for a finished gui frame into `encoder`, returning the command
+    /// buffers from Egui's paint callbacks like `render_to_encoder`
+    pub fn paint(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        frame: EguiFrame,
+    ) -> Vec<wgpu::CommandBuffer> {
+        let screen_descriptor = ScreenDescriptor {
+            size_in_pixels: frame.size_in_pixels,
+            pixels_per_point: frame.pixels_per_point,
+        };
+
+        let clipped_primitives = self.ctx.tessellate(frame.shapes);
+        let textures_delta = std::mem::take(&mut self.textures_delta);
         let user_cmd_bufs = {
-            for (id, image_delta) in &run_output.textures_delta.set {
-                self.renderer.update_texture(
-                    &wgpu_state.device,
-                    &wgpu_state.queue,
-                    *id,
-                    image_delta,
-                );
+            for (id, image_delta) in &textures_delta.set {
+                self.renderer
+                    .update_texture(device, queue, *id, image_delta);
             }
 
             self.renderer.update_buffers(
-                &wgpu_state.device,
-                &wgpu_state.queue,
-                &mut encoder,
+                device,
+                queue,
+                encoder,
                 &clipped_primitives,
                 &screen_descriptor,
             )
@@ -201,7 +1040,7 @@ impl EguiManager {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Egui render pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Load,
@@ -215,13 +1054,53 @@ impl EguiManager {
                 .render(&mut render_pass, &clipped_primitives, &screen_descriptor);
         }
 
-        for id in &run_output.textures_delta.free {
+        // Freed only after the render pass, an earlier frame painted now may still use them
+        for id in &textures_delta.free {
             self.renderer.free_texture(id);
         }
 
-        let encoded = encoder.finish();
-        wgpu_state
-            .queue
-            .submit(user_cmd_bufs.into_iter().chain(std::iter::once(encoded)));
+        user_cmd_bufs
     }
 }
+
+fn create_egui_encoder(device: &wgpu::Device) -> wgpu::CommandEncoder {
+    device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Egui command encoder"),
+    })
+}
+
+/// Submit Egui's paint callback command buffers followed by the encoder
+fn submit_egui_encoder(
+    queue: &wgpu::Queue,
+    encoder: wgpu::CommandEncoder,
+    user_cmd_bufs: Vec<wgpu::CommandBuffer>,
+) {
+    queue.submit(
+        user_cmd_bufs
+            .into_iter()
+            .chain(std::iter::once(encoder.finish())),
+    );
+}
+
+fn create_render_target_texture(
+    device: &wgpu::Device,
+    size: [u32; 2],
+    format: TextureFormat,
+) -> (wgpu::Texture, wgpu::TextureView) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Egui render target"),
+        size: wgpu::Extent3d {
+            width: size[0].max(1),
+            height: size[1].max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
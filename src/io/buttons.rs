@@ -0,0 +1,77 @@
+use std::collections::{hash_map::Entry, HashMap};
+use std::hash::Hash;
+
+/// Held state and per-frame transition counts for a set of keys or buttons.
+///
+/// Counting transitions instead of keeping a single "changed this frame" flag means a key that is
+/// pressed and released again between two frames still reports both the press and the release.
+pub(crate) struct ButtonStates<K> {
+    /// Held keys and the time they went down at
+    held: HashMap<K, f64>,
+    time: f64,
+    presses: HashMap<K, u32>,
+    releases: HashMap<K, u32>,
+}
+
+impl<K: Copy + Eq + Hash> ButtonStates<K> {
+    pub fn new() -> ButtonStates<K> {
+        ButtonStates {
+            held: HashMap::new(),
+            time: 0.0,
+            presses: HashMap::new(),
+            releases: HashMap::new(),
+        }
+    }
+
+    /// Records a press, OS key repeats of an already held key are not counted
+    pub fn press(&mut self, key: K) {
+        if let Entry::Vacant(entry) = self.held.entry(key) {
+            entry.insert(self.time);
+            *self.presses.entry(key).or_default() += 1;
+        }
+    }
+
+    /// Records a release, even if the press happened before we started receiving events
+    pub fn release(&mut self, key: K) {
+        self.held.remove(&key);
+        *self.releases.entry(key).or_default() += 1;
+    }
+
+    pub fn is_pressed(&self, key: &K) -> bool {
+        self.held.contains_key(key)
+    }
+
+    /// How long the key has been held down for in seconds
+    pub fn held_duration(&self, key: &K) -> Option<f64> {
+        self.held.get(key).map(|start| self.time - start)
+    }
+
+    /// All held keys and how long they have been held down for in seconds
+    pub fn held(&self) -> impl Iterator<Item = (K, f64)> + '_ {
+        self.held
+            .iter()
+            .map(|(key, start)| (*key, self.time - start))
+    }
+
+    /// The time in seconds that presses and releases are currently being recorded at
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        self.time += dt as f64;
+    }
+
+    pub fn press_count(&self, key: &K) -> u32 {
+        self.presses.get(key).copied().unwrap_or(0)
+    }
+
+    pub fn release_count(&self, key: &K) -> u32 {
+        self.releases.get(key).copied().unwrap_or(0)
+    }
+
+    pub fn next_frame(&mut self) {
+        self.presses.clear();
+        self.releases.clear();
+    }
+}
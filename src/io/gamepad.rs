@@ -0,0 +1,473 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+use super::buttons::ButtonStates;
+
+/// Digital gamepad buttons, named by position on a standard (Xbox style) controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    /// A on Xbox controllers, Cross on PlayStation controllers
+    South,
+    /// B on Xbox controllers, Circle on PlayStation controllers
+    East,
+    /// Y on Xbox controllers, Triangle on PlayStation controllers
+    North,
+    /// X on Xbox controllers, Square on PlayStation controllers
+    West,
+    LeftBumper,
+    RightBumper,
+    /// The left trigger crossing its digital threshold, use `GamepadAxis::LeftTrigger` for the analog value
+    LeftTrigger,
+    /// The right trigger crossing its digital threshold, use `GamepadAxis::RightTrigger` for the analog value
+    RightTrigger,
+    Select,
+    Start,
+    /// The guide/home button
+    Mode,
+    /// Pressing in the left stick
+    LeftStick,
+    /// Pressing in the right stick
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// Analog gamepad inputs, sticks range from -1 to 1 (up is positive) and triggers from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+/// Gamepad input as reported by a `GamepadBackend`, gamepads are identified by their index
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadEvent {
+    /// A gamepad with the given name was connected
+    Connected(usize, String),
+    Disconnected(usize),
+    ButtonPressed(usize, GamepadButton),
+    ButtonReleased(usize, GamepadButton),
+    AxisChanged(usize, GamepadAxis, f32),
+}
+
+/// A source of gamepad events, implemented by `GilrsBackend` and by `MockBackend` for tests
+pub trait GamepadBackend {
+    /// Push all gamepad events that happened since the last call onto `events`
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>);
+}
+
+/// Deadzones applied to the analog inputs, values inside the deadzone read as 0 and values outside
+/// are rescaled so they still start at 0 and reach 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deadzones {
+    /// Radial deadzone for both sticks
+    pub stick: f32,
+    /// Deadzone for both triggers
+    pub trigger: f32,
+}
+
+impl Default for Deadzones {
+    fn default() -> Self {
+        Deadzones {
+            stick: 0.15,
+            trigger: 0.05,
+        }
+    }
+}
+
+fn rescale(value: f32, deadzone: f32) -> f32 {
+    if value.abs() <= deadzone {
+        0.0
+    } else {
+        value.signum() * ((value.abs() - deadzone) / (1.0 - deadzone)).min(1.0)
+    }
+}
+
+/// The state of a single connected gamepad
+pub struct Gamepad {
+    name: String,
+    buttons: ButtonStates<GamepadButton>,
+    axes: HashMap<GamepadAxis, f32>,
+    deadzones: Deadzones,
+}
+
+impl Gamepad {
+    fn new(name: String, deadzones: Deadzones) -> Gamepad {
+        Gamepad {
+            name,
+            buttons: ButtonStates::new(),
+            axes: HashMap::new(),
+            deadzones,
+        }
+    }
+
+    /// The name the gamepad reported when it connected
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns if the button is currently held down
+    pub fn is_pressed(&self, button: GamepadButton) -> bool {
+        self.buttons.is_pressed(&button)
+    }
+
+    /// Returns if the button was pressed down this frame
+    pub fn pressed_this_frame(&self, button: GamepadButton) -> bool {
+        self.buttons.press_count(&button) > 0
+    }
+
+    /// Returns if the button was released this frame
+    pub fn released_this_frame(&self, button: GamepadButton) -> bool {
+        self.buttons.release_count(&button) > 0
+    }
+
+    /// Returns the value of the axis without any deadzone applied
+    pub fn raw_axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+
+    /// Returns the value of the axis with the deadzones applied, stick axes use the radial
+    /// deadzone of their stick
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        match axis {
+            GamepadAxis::LeftStickX => self.left_stick().0,
+            GamepadAxis::LeftStickY => self.left_stick().1,
+            GamepadAxis::RightStickX => self.right_stick().0,
+            GamepadAxis::RightStickY => self.right_stick().1,
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => {
+                rescale(self.raw_axis(axis), self.deadzones.trigger)
+            }
+        }
+    }
+
+    fn stick(&self, x: GamepadAxis, y: GamepadAxis) -> (f32, f32) {
+        let (x, y) = (self.raw_axis(x), self.raw_axis(y));
+        let length = x.hypot(y);
+        if length <= self.deadzones.stick {
+            return (0.0, 0.0);
+        }
+        let scale = rescale(length, self.deadzones.stick) / length;
+        (x * scale, y * scale)
+    }
+
+    /// Returns the position of the left stick with the deadzone applied
+    pub fn left_stick(&self) -> (f32, f32) {
+        self.stick(GamepadAxis::LeftStickX, GamepadAxis::LeftStickY)
+    }
+
+    /// Returns the position of the right stick with the deadzone applied
+    pub fn right_stick(&self) -> (f32, f32) {
+        self.stick(GamepadAxis::RightStickX, GamepadAxis::RightStickY)
+    }
+
+    /// Returns how far the left trigger is pulled with the deadzone applied
+    pub fn left_trigger(&self) -> f32 {
+        self.axis(GamepadAxis::LeftTrigger)
+    }
+
+    /// Returns how far the right trigger is pulled with the deadzone applied
+    pub fn right_trigger(&self) -> f32 {
+        self.axis(GamepadAxis::RightTrigger)
+    }
+}
+
+/// Keeps track of all gamepads, indexed by the id the backend gives them. Indices of disconnected
+/// gamepads may be reused by the backend for the next gamepad that connects.
+pub struct Gamepads {
+    backend: Option<Box<dyn GamepadBackend>>,
+    pads: Vec<Option<Gamepad>>,
+    events: Vec<GamepadEvent>,
+    deadzones: Deadzones,
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Gamepads {
+    /// Create a `Gamepads` using gilrs to talk to the gamepads, if gilrs fails to initialise no
+    /// gamepads will be reported
+    pub fn new() -> Gamepads {
+        match GilrsBackend::new() {
+            Ok(backend) => Gamepads::with_backend(Box::new(backend)),
+            Err(e) => {
+                log::error!("Couldn't initialise gamepad support: {}", e);
+                Gamepads::without_backend()
+            }
+        }
+    }
+
+    /// Create a `Gamepads` that receives events from the given backend
+    pub fn with_backend(backend: Box<dyn GamepadBackend>) -> Gamepads {
+        Gamepads {
+            backend: Some(backend),
+            ..Gamepads::without_backend()
+        }
+    }
+
+    fn without_backend() -> Gamepads {
+        Gamepads {
+            backend: None,
+            pads: Vec::new(),
+            events: Vec::new(),
+            deadzones: Deadzones::default(),
+        }
+    }
+
+    /// Applies a single event, `poll` calls this for every event the backend reports
+    pub fn handle_event(&mut self, event: &GamepadEvent) {
+        match event {
+            GamepadEvent::Connected(id, name) => {
+                if self.pads.len() <= *id {
+                    self.pads.resize_with(id + 1, || None);
+                }
+                self.pads[*id] = Some(Gamepad::new(name.clone(), self.deadzones));
+            }
+            GamepadEvent::Disconnected(id) => {
+                if let Some(pad) = self.pads.get_mut(*id) {
+                    *pad = None;
+                }
+            }
+            GamepadEvent::ButtonPressed(id, button) => {
+                if let Some(pad) = self.get_mut(*id) {
+                    pad.buttons.press(*button);
+                }
+            }
+            GamepadEvent::ButtonReleased(id, button) => {
+                if let Some(pad) = self.get_mut(*id) {
+                    pad.buttons.release(*button);
+                }
+            }
+            GamepadEvent::AxisChanged(id, axis, value) => {
+                if let Some(pad) = self.get_mut(*id) {
+                    pad.axes.insert(*axis, *value);
+                }
+            }
+        }
+        self.events.push(event.clone());
+    }
+
+    /// Fetches new events from the backend, this function is called automatically before
+    /// `Application::update` so you shouldn't need to call this function yourself.
+    pub fn poll(&mut self) {
+        let mut events = Vec::new();
+        if let Some(backend) = &mut self.backend {
+            backend.poll(&mut events);
+        }
+        for event in &events {
+            self.handle_event(event);
+        }
+    }
+
+    /// Resets the gamepads for the next frame, this function is called automatically so you shouldn't need to call this function yourself.
+    pub fn next_frame(&mut self) {
+        self.events.clear();
+        for pad in self.pads.iter_mut().flatten() {
+            pad.buttons.next_frame();
+        }
+    }
+
+    /// Returns the gamepad with this index if it's connected
+    pub fn get(&self, id: usize) -> Option<&Gamepad> {
+        self.pads.get(id).and_then(Option::as_ref)
+    }
+
+    fn get_mut(&mut self, id: usize) -> Option<&mut Gamepad> {
+        self.pads.get_mut(id).and_then(Option::as_mut)
+    }
+
+    /// Returns all connected gamepads along with their index
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Gamepad)> {
+        self.pads
+            .iter()
+            .enumerate()
+            .filter_map(|(id, pad)| pad.as_ref().map(|pad| (id, pad)))
+    }
+
+    /// Returns the connected gamepad with the lowest index, handy for single player games
+    pub fn first(&self) -> Option<&Gamepad> {
+        self.iter().next().map(|(_, pad)| pad)
+    }
+
+    /// Returns all gamepad events that happened this frame, including connects and disconnects
+    pub fn events(&self) -> &[GamepadEvent] {
+        &self.events
+    }
+
+    /// Returns the indices of gamepads that were connected this frame
+    pub fn connected_this_frame(&self) -> impl Iterator<Item = usize> + '_ {
+        self.events.iter().filter_map(|event| match event {
+            GamepadEvent::Connected(id, _) => Some(*id),
+            _ => None,
+        })
+    }
+
+    /// Returns the indices of gamepads that were disconnected this frame
+    pub fn disconnected_this_frame(&self) -> impl Iterator<Item = usize> + '_ {
+        self.events.iter().filter_map(|event| match event {
+            GamepadEvent::Disconnected(id) => Some(*id),
+            _ => None,
+        })
+    }
+
+    /// Set the deadzones applied to the analog inputs of all gamepads
+    pub fn set_deadzones(&mut self, deadzones: Deadzones) {
+        self.deadzones = deadzones;
+        for pad in self.pads.iter_mut().flatten() {
+            pad.deadzones = deadzones;
+        }
+    }
+
+    /// Returns the deadzones applied to the analog inputs
+    pub fn deadzones(&self) -> Deadzones {
+        self.deadzones
+    }
+}
+
+/// A `GamepadBackend` using gilrs, this is what `Gamepads::new` uses
+pub struct GilrsBackend {
+    gilrs: gilrs::Gilrs,
+    /// Pads that were already plugged in when gilrs started, gilrs doesn't report these
+    connected: Vec<GamepadEvent>,
+}
+
+impl GilrsBackend {
+    pub fn new() -> Result<GilrsBackend, Box<gilrs::Error>> {
+        // gilrs' own deadzone filter is disabled so only our `Deadzones` apply
+        let gilrs = gilrs::GilrsBuilder::new()
+            .with_default_filters(false)
+            .build()
+            .map_err(Box::new)?;
+        let connected = gilrs
+            .gamepads()
+            .map(|(id, pad)| GamepadEvent::Connected(usize::from(id), pad.name().to_string()))
+            .collect();
+        Ok(GilrsBackend { gilrs, connected })
+    }
+
+    fn button(button: gilrs::Button) -> Option<GamepadButton> {
+        use gilrs::Button;
+
+        Some(match button {
+            Button::South => GamepadButton::South,
+            Button::East => GamepadButton::East,
+            Button::North => GamepadButton::North,
+            Button::West => GamepadButton::West,
+            Button::LeftTrigger => GamepadButton::LeftBumper,
+            Button::RightTrigger => GamepadButton::RightBumper,
+            Button::LeftTrigger2 => GamepadButton::LeftTrigger,
+            Button::RightTrigger2 => GamepadButton::RightTrigger,
+            Button::Select => GamepadButton::Select,
+            Button::Start => GamepadButton::Start,
+            Button::Mode => GamepadButton::Mode,
+            Button::LeftThumb => GamepadButton::LeftStick,
+            Button::RightThumb => GamepadButton::RightStick,
+            Button::DPadUp => GamepadButton::DPadUp,
+            Button::DPadDown => GamepadButton::DPadDown,
+            Button::DPadLeft => GamepadButton::DPadLeft,
+            Button::DPadRight => GamepadButton::DPadRight,
+            Button::C | Button::Z | Button::Unknown => return None,
+        })
+    }
+
+    fn axis(axis: gilrs::Axis) -> Option<GamepadAxis> {
+        use gilrs::Axis;
+
+        Some(match axis {
+            Axis::LeftStickX => GamepadAxis::LeftStickX,
+            Axis::LeftStickY => GamepadAxis::LeftStickY,
+            Axis::RightStickX => GamepadAxis::RightStickX,
+            Axis::RightStickY => GamepadAxis::RightStickY,
+            _ => return None,
+        })
+    }
+}
+
+impl GamepadBackend for GilrsBackend {
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>) {
+        use gilrs::ev::filter::{axis_dpad_to_button, Filter};
+        use gilrs::{Button, EventType};
+
+        events.append(&mut self.connected);
+        while let Some(event) = self.gilrs.next_event() {
+            let Some(event) = event.filter_ev(&axis_dpad_to_button, &mut self.gilrs) else {
+                continue;
+            };
+            let id = usize::from(event.id);
+            match event.event {
+                EventType::Connected => {
+                    let name = self.gilrs.gamepad(event.id).name().to_string();
+                    events.push(GamepadEvent::Connected(id, name));
+                }
+                EventType::Disconnected => events.push(GamepadEvent::Disconnected(id)),
+                EventType::ButtonPressed(button, _) => {
+                    if let Some(button) = Self::button(button) {
+                        events.push(GamepadEvent::ButtonPressed(id, button));
+                    }
+                }
+                EventType::ButtonReleased(button, _) => {
+                    if let Some(button) = Self::button(button) {
+                        events.push(GamepadEvent::ButtonReleased(id, button));
+                    }
+                }
+                // Analog triggers are reported as buttons with a value
+                EventType::ButtonChanged(Button::LeftTrigger2, value, _) => {
+                    events.push(GamepadEvent::AxisChanged(
+                        id,
+                        GamepadAxis::LeftTrigger,
+                        value,
+                    ));
+                }
+                EventType::ButtonChanged(Button::RightTrigger2, value, _) => {
+                    events.push(GamepadEvent::AxisChanged(
+                        id,
+                        GamepadAxis::RightTrigger,
+                        value,
+                    ));
+                }
+                EventType::AxisChanged(axis, value, _) => {
+                    if let Some(axis) = Self::axis(axis) {
+                        events.push(GamepadEvent::AxisChanged(id, axis, value));
+                    }
+                }
+                EventType::ButtonChanged(..)
+                | EventType::ButtonRepeated(..)
+                | EventType::Dropped => {}
+            }
+        }
+        self.gilrs.inc();
+    }
+}
+
+/// A `GamepadBackend` for tests that reports events pushed to it. Clones share the same queue, so
+/// keep a clone around to push events after handing the backend to `Gamepads::with_backend`.
+#[derive(Clone, Default)]
+pub struct MockBackend {
+    queue: Rc<RefCell<VecDeque<GamepadEvent>>>,
+}
+
+impl MockBackend {
+    pub fn new() -> MockBackend {
+        MockBackend::default()
+    }
+
+    /// Queue an event to be reported by the next `poll`
+    pub fn push(&self, event: GamepadEvent) {
+        self.queue.borrow_mut().push_back(event);
+    }
+}
+
+impl GamepadBackend for MockBackend {
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>) {
+        events.extend(self.queue.borrow_mut().drain(..));
+    }
+}
@@ -1,89 +1,947 @@
+use super::buttons::ButtonStates;
 use egui_winit::winit::event::{ElementState, Event, KeyboardInput, WindowEvent};
-use winit::event::VirtualKeyCode;
+use winit::event::{Ime, ModifiersState, ScanCode, VirtualKeyCode};
 
-use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
 
 pub struct Keyboard {
-    keys: HashMap<VirtualKeyCode, bool>,
-    this_frame: HashMap<VirtualKeyCode, bool>,
+    keys: ButtonStates<VirtualKeyCode>,
+    scancodes: ButtonStates<ScanCode>,
+    modifiers: ModifiersState,
+    key_repeat: Option<KeyRepeat>,
+    repeats: Vec<VirtualKeyCode>,
+
+    text_input: Vec<TextInput>,
+    ime_events: Vec<Ime>,
+    ime_preedit: Option<(String, Option<(usize, usize)>)>,
+    /// Keys whose press was captured by Egui and filtered, their release is dropped too
+    captured_scancodes: HashSet<ScanCode>,
+}
+
+/// A single piece of text entry received this frame, in the order it was typed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextInput {
+    /// A printable character was typed, or committed by the IME
+    Char(char),
+    /// Backspace was pressed (including OS key repeat)
+    Backspace,
+    /// Delete was pressed (including OS key repeat)
+    Delete,
+    /// Enter/Return was pressed (including OS key repeat)
+    Enter,
+}
+
+/// Settings for the auto-repeat events generated while a key is held, independent of the OS repeat
+/// settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyRepeat {
+    /// Seconds a key has to be held before it starts repeating
+    pub delay: f32,
+    /// Repeats per second once the delay has passed
+    pub rate: f32,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        KeyRepeat {
+            delay: 0.5,
+            rate: 20.0,
+        }
+    }
+}
+
+impl KeyRepeat {
+    /// How many repeats have happened after a key has been held for `held` seconds
+    fn count(&self, held: f64) -> u64 {
+        let held = held - self.delay as f64;
+        if held < 0.0 {
+            0
+        } else {
+            (held * self.rate as f64) as u64 + 1
+        }
+    }
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Keyboard {
     pub fn new() -> Keyboard {
         Keyboard {
-            keys: HashMap::new(),
-            this_frame: HashMap::new(),
+            keys: ButtonStates::new(),
+            scancodes: ButtonStates::new(),
+            modifiers: ModifiersState::empty(),
+            key_repeat: Some(KeyRepeat::default()),
+            repeats: Vec::new(),
+
+            text_input: Vec::new(),
+            ime_events: Vec::new(),
+            ime_preedit: None,
+            captured_scancodes: HashSet::new(),
         }
     }
 
+    /// Also called for OS key repeats, so holding backspace keeps deleting
     fn press(&mut self, key: VirtualKeyCode) {
-        self.keys.insert(key, true);
-        self.this_frame.insert(key, true);
+        let input = match key {
+            VirtualKeyCode::Back => Some(TextInput::Backspace),
+            VirtualKeyCode::Delete => Some(TextInput::Delete),
+            VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => Some(TextInput::Enter),
+            _ => None,
+        };
+        self.text_input.extend(input);
+        self.keys.press(key);
     }
 
     fn release(&mut self, key: VirtualKeyCode) {
-        self.keys.insert(key, false);
-        self.this_frame.insert(key, true);
+        self.keys.release(key);
+    }
+
+    fn set_scancode(&mut self, scancode: ScanCode, pressed: bool) {
+        if pressed {
+            self.scancodes.press(scancode);
+        } else {
+            self.scancodes.release(scancode);
+        }
+    }
+
+    /// Control characters are dropped, platforms disagree on what they send for backspace and
+    /// delete so those come from `press` instead
+    fn receive_char(&mut self, c: char) {
+        if !c.is_control() {
+            self.text_input.push(TextInput::Char(c));
+        }
+    }
+
+    fn receive_ime(&mut self, ime: &Ime) {
+        match ime {
+            Ime::Preedit(text, cursor) if !text.is_empty() => {
+                self.ime_preedit = Some((text.clone(), *cursor));
+            }
+            Ime::Preedit(..) | Ime::Enabled | Ime::Disabled => {
+                self.ime_preedit = None;
+            }
+            Ime::Commit(text) => {
+                self.ime_preedit = None;
+                self.text_input.extend(text.chars().map(TextInput::Char));
+            }
+        }
+        self.ime_events.push(ime.clone());
     }
 
     /// This function is called automatically in the application loop, you shouldn't be calling this yourself.
     pub fn handle_event(&mut self, event: &Event<()>) {
+        match event {
+            Event::WindowEvent {
+                window_id: _,
+                event:
+                    WindowEvent::KeyboardInput {
+                        device_id: _,
+                        input:
+                            KeyboardInput {
+                                scancode,
+                                state,
+                                virtual_keycode,
+                                ..
+                            },
+                        is_synthetic: _,
+                    },
+            } => {
+                let pressed = state == &ElementState::Pressed;
+                if !pressed && self.captured_scancodes.remove(scancode) {
+                    return;
+                }
+                self.set_scancode(*scancode, pressed);
+                match virtual_keycode {
+                    Some(key) if pressed => self.press(*key),
+                    Some(key) => self.release(*key),
+                    None => {}
+                }
+            }
+            Event::WindowEvent {
+                window_id: _,
+                event: WindowEvent::ModifiersChanged(modifiers),
+            } => {
+                self.modifiers = *modifiers;
+            }
+            Event::WindowEvent {
+                window_id: _,
+                event: WindowEvent::ReceivedCharacter(c),
+            } => {
+                self.receive_char(*c);
+            }
+            Event::WindowEvent {
+                window_id: _,
+                event: WindowEvent::Ime(ime),
+            } => {
+                self.receive_ime(ime);
+            }
+            _ => {}
+        }
+    }
+
+    /// Called by `Context` instead of `handle_event` for events Egui captured while
+    /// `filter_gui_captured_input` is set, so the release of a filtered press is dropped as well
+    pub fn handle_captured_event(&mut self, event: &Event<()>) {
         if let Event::WindowEvent {
             window_id: _,
             event:
                 WindowEvent::KeyboardInput {
-                    device_id: _,
                     input:
                         KeyboardInput {
-                            scancode: _,
-                            state,
-                            virtual_keycode: Some(key),
-                            modifiers: _,
+                            scancode,
+                            state: ElementState::Pressed,
+                            ..
                         },
-                    is_synthetic: _,
+                    ..
                 },
         } = event
         {
-            if state == &ElementState::Pressed {
-                self.press(*key);
-            } else {
-                self.release(*key);
+            // A key that was already held keeps its release, otherwise it would get stuck
+            if !self.scancodes.is_pressed(scancode) {
+                self.captured_scancodes.insert(*scancode);
             }
         }
     }
 
-    /// Returns if this key was pressed down on this frame
+    /// Returns if this key was pressed down on this frame, this is still true if it was also
+    /// released again before the frame ended
     pub fn pressed_this_frame(&self, key: &VirtualKeyCode) -> bool {
-        match self.keys.get(key) {
-            None | Some(false) => false,
-            Some(true) => match self.this_frame.get(key) {
-                None | Some(false) => false,
-                Some(true) => true,
-            },
-        }
+        self.keys.press_count(key) > 0
     }
 
-    /// Returns if this key was released on this frame
+    /// Returns if this key was released on this frame, this is still true if it was also pressed
+    /// again before the frame ended
     pub fn released_this_frame(&self, key: &VirtualKeyCode) -> bool {
-        match self.keys.get(key) {
-            Some(true) => false,
-            None | Some(false) => match self.this_frame.get(key) {
-                None | Some(false) => false,
-                Some(true) => true,
-            },
-        }
+        self.keys.release_count(key) > 0
+    }
+
+    /// Returns how many times this key went down on this frame, OS key repeats are not counted
+    pub fn press_count(&self, key: &VirtualKeyCode) -> u32 {
+        self.keys.press_count(key)
+    }
+
+    /// Returns how many times this key was released on this frame
+    pub fn release_count(&self, key: &VirtualKeyCode) -> u32 {
+        self.keys.release_count(key)
     }
 
     /// Returns if the key is currently held down
     pub fn is_pressed(&self, key: &VirtualKeyCode) -> bool {
-        match self.keys.get(key) {
-            None | Some(false) => false,
-            Some(true) => true,
+        self.keys.is_pressed(key)
+    }
+
+    /// Returns if the key with this physical scancode was pressed down on this frame.
+    /// Scancodes are platform specific, see `PhysicalKey` for a portable way to query common keys.
+    pub fn scancode_pressed_this_frame(&self, scancode: ScanCode) -> bool {
+        self.scancodes.press_count(&scancode) > 0
+    }
+
+    /// Returns if the key with this physical scancode was released on this frame
+    pub fn scancode_released_this_frame(&self, scancode: ScanCode) -> bool {
+        self.scancodes.release_count(&scancode) > 0
+    }
+
+    /// Returns if the key with this physical scancode is currently held down
+    pub fn is_scancode_pressed(&self, scancode: ScanCode) -> bool {
+        self.scancodes.is_pressed(&scancode)
+    }
+
+    /// Returns if the key in this physical position was pressed down on this frame, regardless of
+    /// the keyboard layout
+    pub fn physical_pressed_this_frame(&self, key: PhysicalKey) -> bool {
+        self.scancode_pressed_this_frame(key.scancode())
+    }
+
+    /// Returns if the key in this physical position was released on this frame, regardless of the
+    /// keyboard layout
+    pub fn physical_released_this_frame(&self, key: PhysicalKey) -> bool {
+        self.scancode_released_this_frame(key.scancode())
+    }
+
+    /// Returns if the key in this physical position is currently held down, regardless of the
+    /// keyboard layout
+    pub fn is_physical_pressed(&self, key: PhysicalKey) -> bool {
+        self.is_scancode_pressed(key.scancode())
+    }
+
+    /// Returns how long the key has been held down for in seconds, or 0 if it isn't held
+    pub fn held_duration(&self, key: &VirtualKeyCode) -> f32 {
+        self.keys.held_duration(key).unwrap_or(0.0) as f32
+    }
+
+    /// Set the auto-repeat settings, or `None` to disable auto-repeat
+    pub fn set_key_repeat(&mut self, repeat: Option<KeyRepeat>) {
+        self.key_repeat = repeat;
+    }
+
+    /// Returns the current auto-repeat settings
+    pub fn key_repeat(&self) -> Option<KeyRepeat> {
+        self.key_repeat
+    }
+
+    /// Returns the auto-repeat events generated this frame, a key appears once for each time it
+    /// repeated. The initial press is not included.
+    pub fn repeats_this_frame(&self) -> &[VirtualKeyCode] {
+        &self.repeats
+    }
+
+    /// Returns how many times this key auto-repeated this frame
+    pub fn repeat_count(&self, key: &VirtualKeyCode) -> u32 {
+        self.repeats.iter().filter(|k| *k == key).count() as u32
+    }
+
+    /// Returns if this key was pressed down or auto-repeated this frame, useful for things like
+    /// moving through a menu while a key is held
+    pub fn pressed_or_repeated(&self, key: &VirtualKeyCode) -> bool {
+        self.pressed_this_frame(key) || self.repeat_count(key) > 0
+    }
+
+    /// Advances held durations and generates this frame's auto-repeat events, this function is
+    /// called automatically before `Application::update` so you shouldn't need to call this
+    /// function yourself.
+    pub fn advance_time(&mut self, dt: f32) {
+        if let Some(repeat) = self.key_repeat {
+            for (key, held) in self.keys.held() {
+                let count = repeat.count(held + dt as f64) - repeat.count(held);
+                self.repeats
+                    .extend(std::iter::repeat_n(key, count as usize));
+            }
         }
+        self.keys.advance(dt);
+        self.scancodes.advance(dt);
+    }
+
+    /// Returns the modifier keys (Shift, Ctrl, Alt, Logo) that are currently held down
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    /// Returns if the shortcut's key was pressed down on this frame while exactly the shortcut's
+    /// modifiers were held, so Ctrl+S does not fire when Ctrl+Shift+S is pressed
+    pub fn shortcut_pressed(&self, shortcut: &Shortcut) -> bool {
+        self.modifiers == shortcut.modifiers && self.pressed_this_frame(&shortcut.key)
+    }
+
+    /// Returns all text entry received this frame in the order it happened, including characters
+    /// committed by the IME
+    pub fn text_input(&self) -> &[TextInput] {
+        &self.text_input
+    }
+
+    /// Returns the printable characters typed this frame, ignoring backspace, delete and enter
+    pub fn typed_text(&self) -> String {
+        self.text_input
+            .iter()
+            .filter_map(|input| match input {
+                TextInput::Char(c) => Some(*c),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns how many times backspace was pressed this frame
+    pub fn backspace_count(&self) -> usize {
+        self.count_input(TextInput::Backspace)
+    }
+
+    /// Returns how many times enter was pressed this frame
+    pub fn enter_count(&self) -> usize {
+        self.count_input(TextInput::Enter)
+    }
+
+    fn count_input(&self, input: TextInput) -> usize {
+        self.text_input.iter().filter(|i| **i == input).count()
+    }
+
+    /// Applies this frame's text entry to the end of `text`: characters are appended and
+    /// backspace removes the last character. Returns true if enter was pressed, e.g. to submit a
+    /// chat message. Characters typed after the enter in the same frame are still appended.
+    pub fn apply_text_input(&self, text: &mut String) -> bool {
+        let mut submitted = false;
+        for input in &self.text_input {
+            match input {
+                TextInput::Char(c) => text.push(*c),
+                TextInput::Backspace => {
+                    text.pop();
+                }
+                TextInput::Delete => {}
+                TextInput::Enter => submitted = true,
+            }
+        }
+        submitted
+    }
+
+    /// Returns the IME events received this frame
+    pub fn ime_events(&self) -> &[Ime] {
+        &self.ime_events
+    }
+
+    /// Returns the text currently being composed in the IME and the byte range of its cursor,
+    /// if any. This is not part of `text_input` until the IME commits it.
+    pub fn ime_preedit(&self) -> Option<(&str, Option<(usize, usize)>)> {
+        self.ime_preedit
+            .as_ref()
+            .map(|(text, cursor)| (text.as_str(), *cursor))
+    }
+
+    /// Simulates the key being pressed, as if a `KeyboardInput` event was received. Useful for tests
+    /// and for feeding input from other sources.
+    pub fn inject_press(&mut self, key: VirtualKeyCode) {
+        self.press(key);
+    }
+
+    /// Simulates the key being released
+    pub fn inject_release(&mut self, key: VirtualKeyCode) {
+        self.release(key);
+    }
+
+    /// Simulates the key with this physical scancode being pressed or released
+    pub fn inject_scancode(&mut self, scancode: ScanCode, pressed: bool) {
+        self.set_scancode(scancode, pressed);
+    }
+
+    /// Simulates the held modifier keys changing
+    pub fn inject_modifiers(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
+    /// Simulates typing text, each character is received as if by `ReceivedCharacter`. Control
+    /// characters are ignored, use `inject_press` with `Back`, `Delete` or `Return` instead.
+    pub fn inject_text(&mut self, text: &str) {
+        for c in text.chars() {
+            self.receive_char(c);
+        }
+    }
+
+    /// Simulates an IME event
+    pub fn inject_ime(&mut self, ime: Ime) {
+        self.receive_ime(&ime);
     }
 
     /// Resets the Keyboard for the next frame, this function is called automatically so you shouldn't need to call this function yourself.
     pub fn next_frame(&mut self) {
-        self.this_frame.clear();
+        self.keys.next_frame();
+        self.scancodes.next_frame();
+        self.repeats.clear();
+        self.text_input.clear();
+        self.ime_events.clear();
+    }
+}
+
+/// A key combined with an exact set of modifiers, such as Ctrl+Shift+S.
+///
+/// Shortcuts can be parsed from strings like `"Ctrl+S"` or `"ctrl+shift+f5"`. Key names are the
+/// `VirtualKeyCode` variant names in any case, plus a few aliases like `"Esc"` or `"-"`. The
+/// `Display` output parses back into the same shortcut.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    pub modifiers: ModifiersState,
+    pub key: VirtualKeyCode,
+}
+
+impl Shortcut {
+    pub fn new(modifiers: ModifiersState, key: VirtualKeyCode) -> Shortcut {
+        Shortcut { modifiers, key }
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.ctrl() {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.shift() {
+            write!(f, "Shift+")?;
+        }
+        if self.modifiers.alt() {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.logo() {
+            write!(f, "Logo+")?;
+        }
+        write!(f, "{:?}", self.key)
+    }
+}
+
+/// The reason a string could not be parsed into a `Shortcut`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseShortcutError {
+    /// The string didn't contain a key, e.g. `""` or `"Ctrl+"`
+    MissingKey,
+    /// The string contained more than one non-modifier key, e.g. `"A+B"`
+    MultipleKeys,
+    /// A part of the string isn't a known modifier or key name
+    UnknownKey(String),
+}
+
+impl fmt::Display for ParseShortcutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseShortcutError::MissingKey => write!(f, "shortcut has no key"),
+            ParseShortcutError::MultipleKeys => write!(f, "shortcut has more than one key"),
+            ParseShortcutError::UnknownKey(name) => write!(f, "unknown key name \"{}\"", name),
+        }
+    }
+}
+
+impl std::error::Error for ParseShortcutError {}
+
+impl FromStr for Shortcut {
+    type Err = ParseShortcutError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = ModifiersState::empty();
+
+        // A trailing "++" means the key itself is the plus key
+        let s = s.trim();
+        let (s, mut key) = if s == "+" {
+            ("", Some(VirtualKeyCode::Plus))
+        } else if let Some(rest) = s.strip_suffix("++") {
+            (rest, Some(VirtualKeyCode::Plus))
+        } else {
+            (s, None)
+        };
+
+        for part in s.split('+').map(str::trim).filter(|_| !s.is_empty()) {
+            if part.is_empty() {
+                return Err(ParseShortcutError::MissingKey);
+            }
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers |= ModifiersState::CTRL,
+                "shift" => modifiers |= ModifiersState::SHIFT,
+                "alt" | "option" => modifiers |= ModifiersState::ALT,
+                "logo" | "super" | "win" | "cmd" | "command" | "meta" => {
+                    modifiers |= ModifiersState::LOGO
+                }
+                name => {
+                    if key.is_some() {
+                        return Err(ParseShortcutError::MultipleKeys);
+                    }
+                    key = Some(
+                        key_from_name(name)
+                            .ok_or_else(|| ParseShortcutError::UnknownKey(part.to_string()))?,
+                    );
+                }
+            }
+        }
+
+        key.map(|key| Shortcut { modifiers, key })
+            .ok_or(ParseShortcutError::MissingKey)
+    }
+}
+
+/// Every `VirtualKeyCode`, used to look keys up by their variant name
+const ALL_KEYS: [VirtualKeyCode; 163] = [
+    VirtualKeyCode::Key1,
+    VirtualKeyCode::Key2,
+    VirtualKeyCode::Key3,
+    VirtualKeyCode::Key4,
+    VirtualKeyCode::Key5,
+    VirtualKeyCode::Key6,
+    VirtualKeyCode::Key7,
+    VirtualKeyCode::Key8,
+    VirtualKeyCode::Key9,
+    VirtualKeyCode::Key0,
+    VirtualKeyCode::A,
+    VirtualKeyCode::B,
+    VirtualKeyCode::C,
+    VirtualKeyCode::D,
+    VirtualKeyCode::E,
+    VirtualKeyCode::F,
+    VirtualKeyCode::G,
+    VirtualKeyCode::H,
+    VirtualKeyCode::I,
+    VirtualKeyCode::J,
+    VirtualKeyCode::K,
+    VirtualKeyCode::L,
+    VirtualKeyCode::M,
+    VirtualKeyCode::N,
+    VirtualKeyCode::O,
+    VirtualKeyCode::P,
+    VirtualKeyCode::Q,
+    VirtualKeyCode::R,
+    VirtualKeyCode::S,
+    VirtualKeyCode::T,
+    VirtualKeyCode::U,
+    VirtualKeyCode::V,
+    VirtualKeyCode::W,
+    VirtualKeyCode::X,
+    VirtualKeyCode::Y,
+    VirtualKeyCode::Z,
+    VirtualKeyCode::Escape,
+    VirtualKeyCode::F1,
+    VirtualKeyCode::F2,
+    VirtualKeyCode::F3,
+    VirtualKeyCode::F4,
+    VirtualKeyCode::F5,
+    VirtualKeyCode::F6,
+    VirtualKeyCode::F7,
+    VirtualKeyCode::F8,
+    VirtualKeyCode::F9,
+    VirtualKeyCode::F10,
+    VirtualKeyCode::F11,
+    VirtualKeyCode::F12,
+    VirtualKeyCode::F13,
+    VirtualKeyCode::F14,
+    VirtualKeyCode::F15,
+    VirtualKeyCode::F16,
+    VirtualKeyCode::F17,
+    VirtualKeyCode::F18,
+    VirtualKeyCode::F19,
+    VirtualKeyCode::F20,
+    VirtualKeyCode::F21,
+    VirtualKeyCode::F22,
+    VirtualKeyCode::F23,
+    VirtualKeyCode::F24,
+    VirtualKeyCode::Snapshot,
+    VirtualKeyCode::Scroll,
+    VirtualKeyCode::Pause,
+    VirtualKeyCode::Insert,
+    VirtualKeyCode::Home,
+    VirtualKeyCode::Delete,
+    VirtualKeyCode::End,
+    VirtualKeyCode::PageDown,
+    VirtualKeyCode::PageUp,
+    VirtualKeyCode::Left,
+    VirtualKeyCode::Up,
+    VirtualKeyCode::Right,
+    VirtualKeyCode::Down,
+    VirtualKeyCode::Back,
+    VirtualKeyCode::Return,
+    VirtualKeyCode::Space,
+    VirtualKeyCode::Compose,
+    VirtualKeyCode::Caret,
+    VirtualKeyCode::Numlock,
+    VirtualKeyCode::Numpad0,
+    VirtualKeyCode::Numpad1,
+    VirtualKeyCode::Numpad2,
+    VirtualKeyCode::Numpad3,
+    VirtualKeyCode::Numpad4,
+    VirtualKeyCode::Numpad5,
+    VirtualKeyCode::Numpad6,
+    VirtualKeyCode::Numpad7,
+    VirtualKeyCode::Numpad8,
+    VirtualKeyCode::Numpad9,
+    VirtualKeyCode::NumpadAdd,
+    VirtualKeyCode::NumpadDivide,
+    VirtualKeyCode::NumpadDecimal,
+    VirtualKeyCode::NumpadComma,
+    VirtualKeyCode::NumpadEnter,
+    VirtualKeyCode::NumpadEquals,
+    VirtualKeyCode::NumpadMultiply,
+    VirtualKeyCode::NumpadSubtract,
+    VirtualKeyCode::AbntC1,
+    VirtualKeyCode::AbntC2,
+    VirtualKeyCode::Apostrophe,
+    VirtualKeyCode::Apps,
+    VirtualKeyCode::Asterisk,
+    VirtualKeyCode::At,
+    VirtualKeyCode::Ax,
+    VirtualKeyCode::Backslash,
+    VirtualKeyCode::Calculator,
+    VirtualKeyCode::Capital,
+    VirtualKeyCode::Colon,
+    VirtualKeyCode::Comma,
+    VirtualKeyCode::Convert,
+    VirtualKeyCode::Equals,
+    VirtualKeyCode::Grave,
+    VirtualKeyCode::Kana,
+    VirtualKeyCode::Kanji,
+    VirtualKeyCode::LAlt,
+    VirtualKeyCode::LBracket,
+    VirtualKeyCode::LControl,
+    VirtualKeyCode::LShift,
+    VirtualKeyCode::LWin,
+    VirtualKeyCode::Mail,
+    VirtualKeyCode::MediaSelect,
+    VirtualKeyCode::MediaStop,
+    VirtualKeyCode::Minus,
+    VirtualKeyCode::Mute,
+    VirtualKeyCode::MyComputer,
+    VirtualKeyCode::NavigateForward,
+    VirtualKeyCode::NavigateBackward,
+    VirtualKeyCode::NextTrack,
+    VirtualKeyCode::NoConvert,
+    VirtualKeyCode::OEM102,
+    VirtualKeyCode::Period,
+    VirtualKeyCode::PlayPause,
+    VirtualKeyCode::Plus,
+    VirtualKeyCode::Power,
+    VirtualKeyCode::PrevTrack,
+    VirtualKeyCode::RAlt,
+    VirtualKeyCode::RBracket,
+    VirtualKeyCode::RControl,
+    VirtualKeyCode::RShift,
+    VirtualKeyCode::RWin,
+    VirtualKeyCode::Semicolon,
+    VirtualKeyCode::Slash,
+    VirtualKeyCode::Sleep,
+    VirtualKeyCode::Stop,
+    VirtualKeyCode::Sysrq,
+    VirtualKeyCode::Tab,
+    VirtualKeyCode::Underline,
+    VirtualKeyCode::Unlabeled,
+    VirtualKeyCode::VolumeDown,
+    VirtualKeyCode::VolumeUp,
+    VirtualKeyCode::Wake,
+    VirtualKeyCode::WebBack,
+    VirtualKeyCode::WebFavorites,
+    VirtualKeyCode::WebForward,
+    VirtualKeyCode::WebHome,
+    VirtualKeyCode::WebRefresh,
+    VirtualKeyCode::WebSearch,
+    VirtualKeyCode::WebStop,
+    VirtualKeyCode::Yen,
+    VirtualKeyCode::Copy,
+    VirtualKeyCode::Paste,
+    VirtualKeyCode::Cut,
+];
+
+/// Looks up a key by its (lowercase) name, accepting every `VirtualKeyCode` variant name, which is
+/// what `Shortcut`'s `Display` prints, as well as a few common aliases
+fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+    use VirtualKeyCode::*;
+
+    const DIGITS: [VirtualKeyCode; 10] =
+        [Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9];
+
+    let bytes = name.as_bytes();
+    if bytes.len() == 1 && bytes[0].is_ascii_digit() {
+        return Some(DIGITS[(bytes[0] - b'0') as usize]);
+    }
+
+    let alias = match name {
+        "esc" => Some(Escape),
+        "enter" => Some(Return),
+        "backspace" => Some(Back),
+        "ins" => Some(Insert),
+        "del" => Some(Delete),
+        "pgup" => Some(PageUp),
+        "pgdn" => Some(PageDown),
+        "-" => Some(Minus),
+        "=" => Some(Equals),
+        "," => Some(Comma),
+        "." => Some(Period),
+        "/" => Some(Slash),
+        "\\" => Some(Backslash),
+        ";" => Some(Semicolon),
+        "'" => Some(Apostrophe),
+        "backtick" | "`" => Some(Grave),
+        "[" => Some(LBracket),
+        "]" => Some(RBracket),
+        "printscreen" => Some(Snapshot),
+        "scrolllock" => Some(Scroll),
+        _ => None,
+    };
+    alias.or_else(|| {
+        ALL_KEYS
+            .into_iter()
+            .find(|key| format!("{:?}", key).eq_ignore_ascii_case(name))
+    })
+}
+
+/// A key identified by its physical position on a US QWERTY keyboard rather than by the character
+/// it produces, so `PhysicalKey::W` is the key above `S` even on AZERTY or Dvorak layouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PhysicalKey {
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    Key0,
+
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+
+    Escape,
+    Grave,
+    Tab,
+    CapsLock,
+    Space,
+    Return,
+    Back,
+
+    LShift,
+    LControl,
+    LAlt,
+    RShift,
+    RControl,
+    RAlt,
+
+    Left,
+    Up,
+    Right,
+    Down,
+}
+
+impl PhysicalKey {
+    /// The scancode winit reports for this key on the current platform
+    #[cfg(not(target_os = "macos"))]
+    pub fn scancode(self) -> ScanCode {
+        use PhysicalKey::*;
+
+        // Windows reports PS/2 set 1 scancodes with 0xE0 prefixed for extended keys, Linux
+        // reports evdev codes which match set 1 for the main block of the keyboard
+        let extended = |windows: ScanCode, linux: ScanCode| {
+            if cfg!(target_os = "windows") {
+                windows
+            } else {
+                linux
+            }
+        };
+
+        match self {
+            Escape => 1,
+            Key1 => 2,
+            Key2 => 3,
+            Key3 => 4,
+            Key4 => 5,
+            Key5 => 6,
+            Key6 => 7,
+            Key7 => 8,
+            Key8 => 9,
+            Key9 => 10,
+            Key0 => 11,
+            Back => 14,
+            Tab => 15,
+            Q => 16,
+            W => 17,
+            E => 18,
+            R => 19,
+            T => 20,
+            Y => 21,
+            U => 22,
+            I => 23,
+            O => 24,
+            P => 25,
+            Return => 28,
+            LControl => 29,
+            A => 30,
+            S => 31,
+            D => 32,
+            F => 33,
+            G => 34,
+            H => 35,
+            J => 36,
+            K => 37,
+            L => 38,
+            Grave => 41,
+            LShift => 42,
+            Z => 44,
+            X => 45,
+            C => 46,
+            V => 47,
+            B => 48,
+            N => 49,
+            M => 50,
+            RShift => 54,
+            LAlt => 56,
+            Space => 57,
+            CapsLock => 58,
+            RControl => extended(0xE01D, 97),
+            RAlt => extended(0xE038, 100),
+            Up => extended(0xE048, 103),
+            Left => extended(0xE04B, 105),
+            Right => extended(0xE04D, 106),
+            Down => extended(0xE050, 108),
+        }
+    }
+
+    /// The scancode winit reports for this key on the current platform
+    #[cfg(target_os = "macos")]
+    pub fn scancode(self) -> ScanCode {
+        use PhysicalKey::*;
+
+        // macOS virtual key codes (kVK_*), which are tied to the key position
+        match self {
+            A => 0x00,
+            S => 0x01,
+            D => 0x02,
+            F => 0x03,
+            H => 0x04,
+            G => 0x05,
+            Z => 0x06,
+            X => 0x07,
+            C => 0x08,
+            V => 0x09,
+            B => 0x0B,
+            Q => 0x0C,
+            W => 0x0D,
+            E => 0x0E,
+            R => 0x0F,
+            Y => 0x10,
+            T => 0x11,
+            Key1 => 0x12,
+            Key2 => 0x13,
+            Key3 => 0x14,
+            Key4 => 0x15,
+            Key6 => 0x16,
+            Key5 => 0x17,
+            Key9 => 0x19,
+            Key7 => 0x1A,
+            Key8 => 0x1C,
+            Key0 => 0x1D,
+            O => 0x1F,
+            U => 0x20,
+            I => 0x22,
+            P => 0x23,
+            Return => 0x24,
+            L => 0x25,
+            J => 0x26,
+            K => 0x28,
+            N => 0x2D,
+            M => 0x2E,
+            Tab => 0x30,
+            Space => 0x31,
+            Grave => 0x32,
+            Back => 0x33,
+            Escape => 0x35,
+            LShift => 0x38,
+            CapsLock => 0x39,
+            LAlt => 0x3A,
+            LControl => 0x3B,
+            RShift => 0x3C,
+            RAlt => 0x3D,
+            RControl => 0x3E,
+            Left => 0x7B,
+            Right => 0x7C,
+            Down => 0x7D,
+            Up => 0x7E,
+        }
     }
 }
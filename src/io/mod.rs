@@ -1,2 +1,6 @@
+mod buttons;
+#[cfg(feature = "gamepad")]
+pub mod gamepad;
 pub mod keyboard;
 pub mod mouse;
+pub mod touch;
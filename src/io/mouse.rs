@@ -2,37 +2,127 @@ use egui_winit::winit::event::{
     DeviceEvent, ElementState, Event, MouseButton, MouseScrollDelta, WindowEvent,
 };
 
+use std::collections::{HashMap, HashSet};
+
+use winit::dpi::PhysicalPosition;
+
+use super::buttons::ButtonStates;
+
+/// Keeps track of the mouse, buttons are identified by winit's `MouseButton` so extra buttons such
+/// as back/forward on gaming mice are tracked as `MouseButton::Other` separately from
+/// Left/Middle/Right.
 pub struct Mouse {
-    this_frame: [bool; 10],
-    pressed: [bool; 10],
-    pos: (i32, i32),
+    buttons: ButtonStates<MouseButton>,
+    /// Cursor position in physical pixels
+    pos: (f64, f64),
+    /// Whether `pos` is a real position the cursor has been at inside the window, so the next
+    /// `CursorMoved` can be turned into a delta without jumping from a stale position
+    pos_valid: bool,
+    /// Raw device motion, unaffected by cursor acceleration and the window borders
     delta: (f64, f64),
-    wheel: (f32, f32),
+    /// Movement of the cursor inside the window in physical pixels
+    cursor_delta: (f64, f64),
+    wheel_lines: (f32, f32),
+    wheel_pixels: (f64, f64),
+    lines_to_pixels: f32,
+    scale_factor: f64,
 
     focused: bool,
+    inside_window: bool,
+
+    click_config: ClickConfig,
+    /// The last press of each button that started or continued a multi-click
+    last_clicks: HashMap<MouseButton, Click>,
+    /// How many clicks in a row each button pressed this frame has had
+    clicks: HashMap<MouseButton, u32>,
+    /// Cursor positions at which each held button went down
+    drag_starts: HashMap<MouseButton, (f64, f64)>,
+    /// Buttons whose press was captured by Egui and filtered, their release is dropped too
+    captured_buttons: HashSet<MouseButton>,
+}
+
+/// Thresholds for detecting double and triple clicks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClickConfig {
+    /// Maximum seconds between two presses for them to count as the same multi-click
+    pub interval: f32,
+    /// Maximum distance in pixels the cursor may move between two presses of a multi-click
+    pub distance: f32,
+}
+
+impl Default for ClickConfig {
+    fn default() -> Self {
+        ClickConfig {
+            interval: 0.4,
+            distance: 4.0,
+        }
+    }
+}
+
+struct Click {
+    time: f64,
+    pos: (f64, f64),
+    count: u32,
+}
+
+impl Click {
+    /// Returns if a press at this time and position counts as the next click in the same row
+    fn continued_by(&self, time: f64, pos: (f64, f64), config: &ClickConfig) -> bool {
+        let distance = (pos.0 - self.pos.0).hypot(pos.1 - self.pos.1);
+        time - self.time <= config.interval as f64 && distance <= config.distance as f64
+    }
+}
+
+impl Default for Mouse {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Mouse {
     pub fn new() -> Mouse {
         Mouse {
-            this_frame: [false; 10],
-            pressed: [false; 10],
-            pos: (0, 0),
+            buttons: ButtonStates::new(),
+            pos: (0.0, 0.0),
+            pos_valid: false,
             delta: (0.0, 0.0),
-            wheel: (0.0, 0.0),
+            cursor_delta: (0.0, 0.0),
+            wheel_lines: (0.0, 0.0),
+            wheel_pixels: (0.0, 0.0),
+            lines_to_pixels: 40.0,
+            scale_factor: 1.0,
 
             focused: true,
+            inside_window: false,
+
+            click_config: ClickConfig::default(),
+            last_clicks: HashMap::new(),
+            clicks: HashMap::new(),
+            drag_starts: HashMap::new(),
+            captured_buttons: HashSet::new(),
         }
     }
 
-    fn press_button(&mut self, button: usize) {
-        self.this_frame[button] = true;
-        self.pressed[button] = true;
+    fn press_button(&mut self, button: MouseButton) {
+        if self.buttons.is_pressed(&button) {
+            return;
+        }
+        self.buttons.press(button);
+
+        let time = self.buttons.time();
+        let pos = self.pos;
+        let count = match self.last_clicks.get(&button) {
+            Some(last) if last.continued_by(time, pos, &self.click_config) => last.count + 1,
+            _ => 1,
+        };
+        self.last_clicks.insert(button, Click { time, pos, count });
+        self.clicks.insert(button, count);
+        self.drag_starts.insert(button, pos);
     }
 
-    fn release_button(&mut self, button: usize) {
-        self.this_frame[button] = true;
-        self.pressed[button] = false;
+    fn release_button(&mut self, button: MouseButton) {
+        self.buttons.release(button);
+        self.drag_starts.remove(&button);
     }
 
     fn translate(&mut self, delta: (f64, f64)) {
@@ -40,19 +130,37 @@ impl Mouse {
         self.delta.1 += delta.1;
     }
 
-    fn scroll(&mut self, wheel: (f32, f32)) {
+    fn scroll(&mut self, delta: &MouseScrollDelta) {
         if !self.focused {
             return;
         }
-        self.wheel.0 += wheel.0;
-        self.wheel.1 += wheel.1;
+        match delta {
+            MouseScrollDelta::LineDelta(x, y) => {
+                self.wheel_lines.0 += x;
+                self.wheel_lines.1 += y;
+            }
+            MouseScrollDelta::PixelDelta(pos) => {
+                self.wheel_pixels.0 += pos.x;
+                self.wheel_pixels.1 += pos.y;
+            }
+        }
     }
 
     /// Set the new position for the mouse, updating the delta relative to where it last was
-    fn update_pos(&mut self, pos: (i32, i32)) {
-        // self.delta.0 = (pos.0 - self.pos.0) as f64;
-        // self.delta.1 = (pos.1 - self.pos.1) as f64;
+    fn update_pos(&mut self, pos: (f64, f64)) {
+        if self.pos_valid {
+            self.cursor_delta.0 += pos.0 - self.pos.0;
+            self.cursor_delta.1 += pos.1 - self.pos.1;
+        }
         self.pos = pos;
+        self.pos_valid = true;
+        self.inside_window = true;
+    }
+
+    /// Moves the cursor position without producing a cursor delta, used when the cursor is warped
+    pub(crate) fn warp_to(&mut self, pos: (f64, f64)) {
+        self.pos = pos;
+        self.pos_valid = true;
     }
 
     /// This function is called automatically in the application loop, you shouldn't be calling this yourself.
@@ -67,7 +175,7 @@ impl Mouse {
                     position,
                     ..
                 } => {
-                    self.update_pos((position.x as i32, position.y as i32));
+                    self.update_pos((position.x, position.y));
                 }
                 WindowEvent::MouseInput {
                     device_id: _,
@@ -75,33 +183,28 @@ impl Mouse {
                     button,
                     ..
                 } => {
-                    let mbutton: u16 = match button {
-                        MouseButton::Left => 0,
-                        MouseButton::Middle => 1,
-                        MouseButton::Right => 2,
-                        MouseButton::Other(bnum) => {
-                            if bnum > &9_u16 {
-                                return;
-                            }
-                            *bnum
-                        }
-                    };
-                    let mut pressed = false;
                     if state == &ElementState::Pressed {
-                        pressed = true;
-                    }
-                    if pressed {
-                        self.press_button(mbutton as usize);
-                    } else {
-                        self.release_button(mbutton as usize);
+                        self.press_button(*button);
+                    } else if !self.captured_buttons.remove(button) {
+                        self.release_button(*button);
                     }
                 }
                 WindowEvent::MouseWheel {
                     device_id: _,
-                    delta: MouseScrollDelta::LineDelta(x, y),
+                    delta,
                     ..
                 } => {
-                    self.scroll((*x, *y));
+                    self.scroll(delta);
+                }
+                WindowEvent::CursorEntered { device_id: _ } => {
+                    self.inside_window = true;
+                }
+                WindowEvent::CursorLeft { device_id: _ } => {
+                    self.inside_window = false;
+                    self.pos_valid = false;
+                }
+                WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                    self.scale_factor = *scale_factor;
                 }
                 WindowEvent::Focused(focused) => {
                     self.focused = *focused;
@@ -111,49 +214,255 @@ impl Mouse {
             Event::DeviceEvent {
                 device_id: _,
                 event: DeviceEvent::MouseMotion { delta },
-            } => {
-                if self.focused {
-                    self.translate(*delta);
-                }
+            } if self.focused => {
+                self.translate(*delta);
             }
             _ => {}
         }
     }
 
+    /// Called by `Context` instead of `handle_event` for events Egui captured while
+    /// `filter_gui_captured_input` is set, so the release of a filtered press is dropped as well
+    pub fn handle_captured_event(&mut self, event: &Event<()>) {
+        if let Event::WindowEvent {
+            window_id: _,
+            event:
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button,
+                    ..
+                },
+        } = event
+        {
+            if !self.buttons.is_pressed(button) {
+                self.captured_buttons.insert(*button);
+            }
+        }
+    }
+
     /// Resets the Mouse for the next frame, this function is called automatically so you shouldn't need to call this function yourself.
     pub fn next_frame(&mut self) {
         self.delta = (0.0, 0.0);
-        self.wheel = (0.0, 0.0);
-        self.this_frame = [false; 10];
+        self.cursor_delta = (0.0, 0.0);
+        self.wheel_lines = (0.0, 0.0);
+        self.wheel_pixels = (0.0, 0.0);
+        self.buttons.next_frame();
+        self.clicks.clear();
+    }
+
+    /// Advances held durations and the clock used for multi-click detection, this function is
+    /// called automatically before `Application::update` so you shouldn't need to call this
+    /// function yourself.
+    pub fn advance_time(&mut self, dt: f32) {
+        self.buttons.advance(dt);
     }
 
-    /// Get a tuple containing the x and y position of the mouse inside the window
-    pub fn get_pos(&self) -> (i32, i32) {
+    /// Get a tuple containing the x and y position of the mouse inside the window in physical pixels
+    pub fn get_pos(&self) -> (f64, f64) {
         self.pos
     }
 
-    /// Get the distance in pixels that the mouse has moved since the last frame
+    /// Get the position of the mouse inside the window in logical pixels, i.e. divided by the
+    /// window's scale factor. This is the coordinate space egui uses.
+    pub fn get_logical_pos(&self) -> (f64, f64) {
+        (
+            self.pos.0 / self.scale_factor,
+            self.pos.1 / self.scale_factor,
+        )
+    }
+
+    /// Set the scale factor used to convert between physical and logical positions, this is kept
+    /// up to date automatically once the window's scale factor changes
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.scale_factor = scale_factor;
+    }
+
+    /// Returns the scale factor used to convert between physical and logical positions
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Get the distance in pixels that the mouse has moved since the last frame, this is the same as
+    /// `raw_motion`
     pub fn get_delta(&self) -> (f64, f64) {
         self.delta
     }
 
-    /// Get the vertical and horizontal scroll distance since last frame
+    /// Get the raw motion reported by the mouse since the last frame while the window is focused.
+    /// This keeps changing when the cursor is outside the window or grabbed, so it's the right
+    /// choice for camera controls, but it isn't in window coordinates.
+    pub fn raw_motion(&self) -> (f64, f64) {
+        self.delta
+    }
+
+    /// Get how far the cursor moved inside the window since the last frame in physical pixels.
+    /// Leaving and re-entering the window doesn't produce a jump.
+    pub fn cursor_delta(&self) -> (f64, f64) {
+        self.cursor_delta
+    }
+
+    /// Returns if the cursor is currently inside the window
+    pub fn is_inside_window(&self) -> bool {
+        self.inside_window
+    }
+
+    /// Returns the cursor position the provided mouse button went down at, if it's currently held
+    pub fn drag_start(&self, button: MouseButton) -> Option<(f64, f64)> {
+        self.drag_starts.get(&button).copied()
+    }
+
+    /// Returns how far the cursor has moved since the provided mouse button went down, if it's
+    /// currently held
+    pub fn drag_delta(&self, button: MouseButton) -> Option<(f64, f64)> {
+        self.drag_start(button)
+            .map(|start| (self.pos.0 - start.0, self.pos.1 - start.1))
+    }
+
+    /// Returns if the provided mouse button is held and the cursor has moved further than the
+    /// click distance threshold since it went down
+    pub fn is_dragging(&self, button: MouseButton) -> bool {
+        self.drag_delta(button)
+            .is_some_and(|(x, y)| x.hypot(y) > self.click_config.distance as f64)
+    }
+
+    /// Get the horizontal and vertical scroll distance since last frame in lines, pixel based
+    /// scrolling from touchpads is converted using `lines_to_pixels`
     pub fn get_scroll(&self) -> (f32, f32) {
-        self.wheel
+        let lines = self.lines_to_pixels as f64;
+        (
+            self.wheel_lines.0 + (self.wheel_pixels.0 / lines) as f32,
+            self.wheel_lines.1 + (self.wheel_pixels.1 / lines) as f32,
+        )
+    }
+
+    /// Get the horizontal and vertical scroll distance since last frame in physical pixels, line
+    /// based scrolling from mouse wheels is converted using `lines_to_pixels`
+    pub fn get_scroll_pixels(&self) -> (f64, f64) {
+        let lines = self.lines_to_pixels as f64;
+        (
+            self.wheel_pixels.0 + self.wheel_lines.0 as f64 * lines,
+            self.wheel_pixels.1 + self.wheel_lines.1 as f64 * lines,
+        )
+    }
+
+    /// Set how many pixels one line of scrolling corresponds to
+    pub fn set_lines_to_pixels(&mut self, pixels: f32) {
+        self.lines_to_pixels = pixels;
+    }
+
+    /// Returns how many pixels one line of scrolling corresponds to
+    pub fn lines_to_pixels(&self) -> f32 {
+        self.lines_to_pixels
     }
 
     /// Returns if the provided mouse button is currently held down
-    pub fn is_pressed(&self, button: usize) -> bool {
-        self.pressed[button]
+    pub fn is_pressed(&self, button: MouseButton) -> bool {
+        self.buttons.is_pressed(&button)
+    }
+
+    /// Returns all mouse buttons that are currently held down, including any extra buttons
+    /// reported as `MouseButton::Other`
+    pub fn pressed_buttons(&self) -> impl Iterator<Item = MouseButton> + '_ {
+        self.buttons.held().map(|(button, _)| button)
+    }
+
+    /// Returns if the provided mouse button was pressed down this frame, this is still true if it
+    /// was also released again before the frame ended
+    pub fn pressed_this_frame(&self, button: MouseButton) -> bool {
+        self.buttons.press_count(&button) > 0
+    }
+
+    /// Returns if the provided mouse button was released this frame, this is still true if it was
+    /// also pressed again before the frame ended
+    pub fn released_this_frame(&self, button: MouseButton) -> bool {
+        self.buttons.release_count(&button) > 0
+    }
+
+    /// Returns how many times the provided mouse button went down this frame
+    pub fn press_count(&self, button: MouseButton) -> u32 {
+        self.buttons.press_count(&button)
+    }
+
+    /// Returns how many times the provided mouse button was released this frame
+    pub fn release_count(&self, button: MouseButton) -> u32 {
+        self.buttons.release_count(&button)
     }
 
-    /// Returns if the provided mouse button was pressed down this frame
-    pub fn pressed_this_frame(&self, button: usize) -> bool {
-        self.pressed[button] && self.this_frame[button]
+    /// Returns how long the provided mouse button has been held down for in seconds, or 0 if it
+    /// isn't held
+    pub fn held_duration(&self, button: MouseButton) -> f32 {
+        self.buttons.held_duration(&button).unwrap_or(0.0) as f32
     }
 
-    /// Returns if the provided mouse button was released this frame
-    pub fn released_this_frame(&self, button: usize) -> bool {
-        !self.pressed[button] && self.this_frame[button]
+    /// Set the thresholds used to detect double and triple clicks
+    pub fn set_click_config(&mut self, config: ClickConfig) {
+        self.click_config = config;
+    }
+
+    /// Returns the thresholds used to detect double and triple clicks
+    pub fn click_config(&self) -> ClickConfig {
+        self.click_config
+    }
+
+    /// Returns 1 for a single click, 2 for a double click and so on if the provided mouse button
+    /// was pressed this frame, otherwise 0
+    pub fn click_count(&self, button: MouseButton) -> u32 {
+        self.clicks.get(&button).copied().unwrap_or(0)
+    }
+
+    /// Returns if the provided mouse button was double clicked this frame
+    pub fn double_clicked(&self, button: MouseButton) -> bool {
+        self.click_count(button) == 2
+    }
+
+    /// Returns if the provided mouse button was triple clicked this frame
+    pub fn triple_clicked(&self, button: MouseButton) -> bool {
+        self.click_count(button) == 3
+    }
+
+    /// Simulates the cursor moving to this position inside the window in physical pixels, as if a
+    /// `CursorMoved` event was received. Useful for tests and for feeding input from other sources.
+    pub fn inject_move(&mut self, x: f64, y: f64) {
+        self.update_pos((x, y));
+    }
+
+    /// Simulates raw mouse motion, as if a `DeviceEvent::MouseMotion` was received
+    pub fn inject_motion(&mut self, dx: f64, dy: f64) {
+        if self.focused {
+            self.translate((dx, dy));
+        }
+    }
+
+    /// Simulates scrolling by this many lines, as if a mouse wheel was turned
+    pub fn inject_scroll(&mut self, x: f32, y: f32) {
+        self.scroll(&MouseScrollDelta::LineDelta(x, y));
+    }
+
+    /// Simulates scrolling by this many physical pixels, as if a touchpad was used
+    pub fn inject_pixel_scroll(&mut self, x: f64, y: f64) {
+        self.scroll(&MouseScrollDelta::PixelDelta(PhysicalPosition::new(x, y)));
+    }
+
+    /// Simulates the button being pressed at the current cursor position
+    pub fn inject_press(&mut self, button: MouseButton) {
+        self.press_button(button);
+    }
+
+    /// Simulates the button being released
+    pub fn inject_release(&mut self, button: MouseButton) {
+        self.release_button(button);
+    }
+
+    /// Simulates the window gaining or losing focus
+    pub fn inject_focus(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Simulates the cursor entering or leaving the window
+    pub fn inject_inside_window(&mut self, inside: bool) {
+        self.inside_window = inside;
+        if !inside {
+            self.pos_valid = false;
+        }
     }
 }
@@ -0,0 +1,301 @@
+use super::mouse::Mouse;
+use egui_winit::winit::event::{Event, MouseButton, Touch as WinitTouch, TouchPhase, WindowEvent};
+
+/// A single finger on the touchscreen
+#[derive(Debug, Clone, PartialEq)]
+pub struct TouchPoint {
+    /// Identifies the finger for as long as it touches the screen
+    pub id: u64,
+    /// `Started` on the frame the finger went down, `Ended`/`Cancelled` on the frame it lifted
+    pub phase: TouchPhase,
+    /// Position in physical pixels
+    pub pos: (f64, f64),
+    /// Position at the start of this frame, or where the finger went down if that was this frame
+    pub prev_pos: (f64, f64),
+    /// Where the finger went down
+    pub start_pos: (f64, f64),
+    /// Pressure from 0 to 1, if the device reports it
+    pub pressure: Option<f64>,
+    start_time: f64,
+}
+
+impl TouchPoint {
+    /// How far the finger moved this frame
+    pub fn delta(&self) -> (f64, f64) {
+        (self.pos.0 - self.prev_pos.0, self.pos.1 - self.prev_pos.1)
+    }
+
+    /// Returns if the finger is still touching the screen
+    pub fn is_active(&self) -> bool {
+        matches!(self.phase, TouchPhase::Started | TouchPhase::Moved)
+    }
+}
+
+/// Thresholds for recognising taps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TapConfig {
+    /// Maximum seconds a finger may touch the screen for it to count as a tap
+    pub max_duration: f32,
+    /// Maximum distance in pixels a finger may move for it to count as a tap
+    pub max_distance: f32,
+}
+
+impl Default for TapConfig {
+    fn default() -> Self {
+        TapConfig {
+            max_duration: 0.3,
+            max_distance: 10.0,
+        }
+    }
+}
+
+/// Keeps track of touch input and recognises taps, pans, pinches and rotations from it.
+/// Trackpad magnify and rotate gestures are reported through the same pinch and rotation queries.
+pub struct Touch {
+    points: Vec<TouchPoint>,
+    primary: Option<u64>,
+    taps: Vec<(f64, f64)>,
+    trackpad_scale: f64,
+    trackpad_rotation: f64,
+
+    time: f64,
+    tap_config: TapConfig,
+    /// If true, the primary touch is fed to the `Mouse` as the left button by
+    /// `handle_event_with_mouse`
+    pub emulate_mouse: bool,
+}
+
+impl Default for Touch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Touch {
+    pub fn new() -> Touch {
+        Touch {
+            points: Vec::new(),
+            primary: None,
+            taps: Vec::new(),
+            trackpad_scale: 1.0,
+            trackpad_rotation: 0.0,
+
+            time: 0.0,
+            tap_config: TapConfig::default(),
+            emulate_mouse: false,
+        }
+    }
+
+    fn touch(&mut self, touch: &WinitTouch) {
+        self.update_touch(
+            touch.id,
+            touch.phase,
+            (touch.location.x, touch.location.y),
+            touch.force.map(|force| force.normalized()),
+        );
+    }
+
+    fn update_touch(&mut self, id: u64, phase: TouchPhase, pos: (f64, f64), pressure: Option<f64>) {
+        if phase == TouchPhase::Started {
+            self.points.retain(|point| point.id != id);
+            if self.active_count() == 0 {
+                self.primary = Some(id);
+            }
+            self.points.push(TouchPoint {
+                id,
+                phase: TouchPhase::Started,
+                pos,
+                prev_pos: pos,
+                start_pos: pos,
+                pressure,
+                start_time: self.time,
+            });
+            return;
+        }
+
+        let Some(point) = self.points.iter_mut().find(|point| point.id == id) else {
+            return;
+        };
+        point.pos = pos;
+        point.pressure = pressure;
+        // Keep reporting `Started` for the rest of the frame the finger went down in
+        if phase != TouchPhase::Moved || point.phase != TouchPhase::Started {
+            point.phase = phase;
+        }
+
+        if matches!(phase, TouchPhase::Ended | TouchPhase::Cancelled) {
+            if self.primary == Some(id) {
+                self.primary = None;
+            }
+            let (dx, dy) = (pos.0 - point.start_pos.0, pos.1 - point.start_pos.1);
+            if phase == TouchPhase::Ended
+                && self.time - point.start_time <= self.tap_config.max_duration as f64
+                && dx.hypot(dy) <= self.tap_config.max_distance as f64
+            {
+                self.taps.push(pos);
+            }
+        }
+    }
+
+    /// This function is called automatically in the application loop, you shouldn't be calling this yourself.
+    pub fn handle_event(&mut self, event: &Event<()>) {
+        if let Event::WindowEvent {
+            window_id: _,
+            event,
+        } = event
+        {
+            match event {
+                WindowEvent::Touch(touch) => self.touch(touch),
+                WindowEvent::TouchpadMagnify { delta, .. } => {
+                    self.trackpad_scale *= 1.0 + delta;
+                }
+                WindowEvent::TouchpadRotate { delta, .. } => {
+                    self.trackpad_rotation += (*delta as f64).to_radians();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Like `handle_event`, but also moves and clicks `mouse` with the primary touch if
+    /// `emulate_mouse` is set. `Context` and `TestContext` call this for every event.
+    pub fn handle_event_with_mouse(&mut self, event: &Event<()>, mouse: &mut Mouse) {
+        let primary = self.primary;
+        self.handle_event(event);
+
+        let Event::WindowEvent {
+            window_id: _,
+            event: WindowEvent::Touch(touch),
+        } = event
+        else {
+            return;
+        };
+        if !self.emulate_mouse {
+            return;
+        }
+
+        let pos = (touch.location.x, touch.location.y);
+        match touch.phase {
+            TouchPhase::Started if self.primary == Some(touch.id) => {
+                mouse.inject_move(pos.0, pos.1);
+                mouse.inject_press(MouseButton::Left);
+            }
+            TouchPhase::Moved if primary == Some(touch.id) => {
+                mouse.inject_move(pos.0, pos.1);
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled if primary == Some(touch.id) => {
+                mouse.inject_move(pos.0, pos.1);
+                mouse.inject_release(MouseButton::Left);
+            }
+            _ => {}
+        }
+    }
+
+    /// Advances the clock used for tap recognition, this function is called automatically before
+    /// `Application::update` so you shouldn't need to call this function yourself.
+    pub fn advance_time(&mut self, dt: f32) {
+        self.time += dt as f64;
+    }
+
+    /// Resets the touches for the next frame, this function is called automatically so you shouldn't need to call this function yourself.
+    pub fn next_frame(&mut self) {
+        self.points.retain(TouchPoint::is_active);
+        for point in &mut self.points {
+            point.phase = TouchPhase::Moved;
+            point.prev_pos = point.pos;
+        }
+        self.taps.clear();
+        self.trackpad_scale = 1.0;
+        self.trackpad_rotation = 0.0;
+    }
+
+    /// Returns all fingers touching the screen this frame in the order they went down, including
+    /// ones that lifted this frame
+    pub fn points(&self) -> &[TouchPoint] {
+        &self.points
+    }
+
+    /// Returns the finger with this id if it touched the screen this frame
+    pub fn get(&self, id: u64) -> Option<&TouchPoint> {
+        self.points.iter().find(|point| point.id == id)
+    }
+
+    /// Returns the first finger that went down while no other finger was touching the screen, for
+    /// as long as it stays down
+    pub fn primary(&self) -> Option<&TouchPoint> {
+        self.primary.and_then(|id| self.get(id))
+    }
+
+    /// Returns how many fingers are currently touching the screen
+    pub fn active_count(&self) -> usize {
+        self.points.iter().filter(|point| point.is_active()).count()
+    }
+
+    /// Returns the positions of all taps that finished this frame
+    pub fn taps(&self) -> &[(f64, f64)] {
+        &self.taps
+    }
+
+    /// Returns the average movement of all fingers this frame
+    pub fn pan_delta(&self) -> (f64, f64) {
+        if self.points.is_empty() {
+            return (0.0, 0.0);
+        }
+        let (x, y) = self.points.iter().fold((0.0, 0.0), |(x, y), point| {
+            let delta = point.delta();
+            (x + delta.0, y + delta.1)
+        });
+        let count = self.points.len() as f64;
+        (x / count, y / count)
+    }
+
+    /// The first two fingers, used for pinch and rotate
+    fn pair(&self) -> Option<(&TouchPoint, &TouchPoint)> {
+        let mut points = self.points.iter();
+        Some((points.next()?, points.next()?))
+    }
+
+    /// Returns how much the distance between the first two fingers (or a trackpad magnify gesture)
+    /// scaled this frame, 1 means no change
+    pub fn pinch_scale(&self) -> f64 {
+        let touch_scale = self.pair().map_or(1.0, |(a, b)| {
+            let before = (a.prev_pos.0 - b.prev_pos.0).hypot(a.prev_pos.1 - b.prev_pos.1);
+            let after = (a.pos.0 - b.pos.0).hypot(a.pos.1 - b.pos.1);
+            if before > 0.0 {
+                after / before
+            } else {
+                1.0
+            }
+        });
+        touch_scale * self.trackpad_scale
+    }
+
+    /// Returns how far the first two fingers (or a trackpad rotate gesture) rotated this frame in
+    /// radians, counterclockwise is positive
+    pub fn rotation(&self) -> f64 {
+        // y points down on screen, so it's flipped to make counterclockwise positive
+        let angle = |a: (f64, f64), b: (f64, f64)| (a.1 - b.1).atan2(b.0 - a.0);
+        let touch_rotation = self.pair().map_or(0.0, |(a, b)| {
+            let delta = angle(a.pos, b.pos) - angle(a.prev_pos, b.prev_pos);
+            // Wrap into -pi..pi so crossing the atan2 discontinuity isn't a full turn
+            (delta + std::f64::consts::PI).rem_euclid(std::f64::consts::TAU) - std::f64::consts::PI
+        });
+        touch_rotation + self.trackpad_rotation
+    }
+
+    /// Simulates a touch event for the finger with this id at this position in physical pixels,
+    /// useful for tests and for feeding input from other sources
+    pub fn inject_touch(&mut self, id: u64, phase: TouchPhase, x: f64, y: f64) {
+        self.update_touch(id, phase, (x, y), None);
+    }
+
+    /// Set the thresholds used to recognise taps
+    pub fn set_tap_config(&mut self, config: TapConfig) {
+        self.tap_config = config;
+    }
+
+    /// Returns the thresholds used to recognise taps
+    pub fn tap_config(&self) -> TapConfig {
+        self.tap_config
+    }
+}
@@ -1,7 +1,15 @@
 use context::{Context, EguiManager, WgpuState};
+#[cfg(feature = "persistence")]
+use persistence::{Storage, WindowGeometry};
 
 pub mod context;
 pub mod io;
+#[cfg(feature = "persistence")]
+pub mod persistence;
+pub mod platform_output;
+#[cfg(feature = "record")]
+pub mod record;
+pub mod testing;
 pub mod timer;
 pub mod utils;
 
@@ -24,6 +32,12 @@ pub trait Application {
     fn close(&mut self, ctx: &Context);
     /// Called a number of times between each frame with all new incoming events for the application
     fn handle_event(&mut self, ctx: &mut Context, event: &Event<()>);
+    /// Identifies the application, the gui state is persisted per app id. Defaults to the name of
+    /// the executable.
+    #[cfg(feature = "persistence")]
+    fn app_id(&self) -> String {
+        persistence::default_app_id()
+    }
 }
 
 /// Create and run a window for this application
@@ -34,6 +48,16 @@ pub trait Application {
 /// * `wb: WindowBuilder` - Settings on how the window should be shaped/sized/positioned/resizable etc
 pub fn run<A: 'static + Application>(app: A, wb: WindowBuilder) {
     let event_loop = winit::event_loop::EventLoopBuilder::<()>::with_user_event().build();
+
+    // Restore the window where it was when the app was last closed
+    #[cfg(feature = "persistence")]
+    let (storage, memory) = Storage::load(persistence::storage_path(&app.app_id()));
+    #[cfg(feature = "persistence")]
+    let wb = match storage.get::<WindowGeometry>(persistence::WINDOW_GEOMETRY_KEY) {
+        Some(geometry) => geometry.apply(wb, &event_loop),
+        None => wb,
+    };
+
     let window = wb.build(&event_loop).unwrap();
 
     let mut adapter_option: Option<Adapter> = None;
@@ -106,6 +130,15 @@ pub fn run<A: 'static + Application>(app: A, wb: WindowBuilder) {
         window,
     };
 
+    #[cfg(feature = "persistence")]
+    let egui = EguiManager::with_storage(
+        &wgpu_state.device,
+        surface_format,
+        &event_loop,
+        storage,
+        memory,
+    );
+    #[cfg(not(feature = "persistence"))]
     let egui = EguiManager::new(&wgpu_state.device, surface_format, &event_loop);
 
     let ctx = Context::new(wgpu_state, egui);
@@ -120,11 +153,21 @@ pub fn run<A: 'static + Application>(app: A, wb: WindowBuilder) {
 /// * `mut app: Application` - the application you want to run
 /// * `mut context: Context` - A wgpu_app Context containing a Display, Egui object and io managers
 /// * `event_loop: EventLoop<()>` - The EventLoop for the window
+///
+/// With the `persistence` feature, create the `EguiManager` with `EguiManager::with_app_id` and the
+/// app's `app_id` so the gui state is stored in the right place.
 pub fn run_with_context<A: 'static + Application>(
     mut app: A,
     mut context: Context,
     event_loop: EventLoop<()>,
 ) {
+    #[cfg(feature = "persistence")]
+    if context.egui.storage().path() != persistence::storage_path(&app.app_id()).as_deref() {
+        log::warn!(
+            "The gui state isn't stored for app id {:?}, create the EguiManager with `with_app_id`",
+            app.app_id()
+        );
+    }
     let mut t = Timer::new();
 
     t.reset();
@@ -146,6 +189,12 @@ pub fn run_with_context<A: 'static + Application>(
                 event: event::WindowEvent::CloseRequested,
             } => {
                 app.close(&context);
+                #[cfg(feature = "record")]
+                context.stop_recording();
+                #[cfg(feature = "persistence")]
+                if let Err(e) = context.egui.save() {
+                    log::error!("Couldn't save gui state: {}", e);
+                }
                 *control_flow = ControlFlow::Exit;
             }
             _ => {
@@ -161,7 +210,16 @@ pub fn run_with_context<A: 'static + Application>(
         // Update
         match t.go() {
             None => {}
-            Some(_) => {
+            Some(delta) => {
+                #[cfg(feature = "record")]
+                context.record_frame(delta);
+
+                context.keyboard.advance_time(delta);
+                context.mouse.advance_time(delta);
+                context.touch.advance_time(delta);
+                #[cfg(feature = "gamepad")]
+                context.gamepads.poll();
+
                 match app.update(&t, &mut context) {
                     Ok(()) => {}
                     Err(wgpu::SurfaceError::Lost) => {
@@ -175,6 +233,9 @@ pub fn run_with_context<A: 'static + Application>(
 
                 context.mouse.next_frame();
                 context.keyboard.next_frame();
+                context.touch.next_frame();
+                #[cfg(feature = "gamepad")]
+                context.gamepads.next_frame();
             }
         }
     });
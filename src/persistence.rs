@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event_loop::EventLoopWindowTarget,
+    window::{Fullscreen, Window, WindowBuilder},
+};
+
+/// Returns the name of the executable, the default `Application::app_id`
+pub fn default_app_id() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| Some(path.file_stem()?.to_str()?.to_owned()))
+        .unwrap_or_else(|| "wgpu_app".to_owned())
+}
+
+/// Returns the directory persisted state of the app with this id is stored in, or `None` if the
+/// platform has no config directory
+pub fn config_dir(app_id: &str) -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join(app_id))
+}
+
+/// Returns the file the `Storage` of the app with this id is stored in
+pub fn storage_path(app_id: &str) -> Option<PathBuf> {
+    Some(config_dir(app_id)?.join("gui.ron"))
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct StoredState<M> {
+    memory: M,
+    values: HashMap<String, String>,
+}
+
+impl<M: Default> Default for StoredState<M> {
+    fn default() -> Self {
+        StoredState {
+            memory: M::default(),
+            values: HashMap::new(),
+        }
+    }
+}
+
+fn invalid_data(e: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Egui memory (window positions, collapsed headers etc.) and app defined values that are kept
+/// between runs, see `EguiManager::storage`. Values are stored as RON strings, so any serde type
+/// can be stored.
+pub struct Storage {
+    path: Option<PathBuf>,
+    values: HashMap<String, String>,
+    last_save: Instant,
+    /// How often the state is saved while the gui is running, `None` to only save when the window
+    /// is closed or `save` is called
+    pub auto_save_interval: Option<Duration>,
+}
+
+impl Storage {
+    /// Load the state stored at `path`, returning the stored Egui memory. Missing or unreadable
+    /// files are logged and start out empty. With no path nothing is loaded or saved.
+    /// `EguiManager` does this for you.
+    pub fn load(path: Option<PathBuf>) -> (Storage, Option<egui::Memory>) {
+        let state = path
+            .as_deref()
+            .filter(|path| path.exists())
+            .and_then(|path| match Storage::read(path) {
+                Ok(state) => Some(state),
+                Err(e) => {
+                    log::warn!("Couldn't load gui state from {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        let storage = Storage {
+            path,
+            values: state.values,
+            last_save: Instant::now(),
+            auto_save_interval: Some(Duration::from_secs(30)),
+        };
+        (storage, state.memory)
+    }
+
+    fn read(path: &Path) -> io::Result<StoredState<Option<egui::Memory>>> {
+        ron::from_str(&fs::read_to_string(path)?).map_err(invalid_data)
+    }
+
+    /// Returns the file the state is stored in
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns the value stored under `key`, or `None` if there is none or it can't be
+    /// deserialized as `T`
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        ron::from_str(self.values.get(key)?).ok()
+    }
+
+    /// Store a value under `key`, replacing any previous value
+    pub fn set<T: Serialize>(&mut self, key: &str, value: &T) {
+        match ron::to_string(value) {
+            Ok(value) => {
+                self.values.insert(key.to_owned(), value);
+            }
+            Err(e) => log::error!("Couldn't serialize value for {}: {}", key, e),
+        }
+    }
+
+    /// Remove the value stored under `key`
+    pub fn remove(&mut self, key: &str) {
+        self.values.remove(key);
+    }
+
+    /// Returns the keys of all stored values
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(String::as_str)
+    }
+
+    /// Write the values and the memory of `ctx` to disk
+    pub fn save(&mut self, ctx: &egui::Context) -> io::Result<()> {
+        self.last_save = Instant::now();
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let state = ctx.memory(|memory| {
+            ron::ser::to_string_pretty(
+                &StoredState {
+                    memory: Some(memory),
+                    values: self.values.clone(),
+                },
+                ron::ser::PrettyConfig::default(),
+            )
+        });
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, state.map_err(invalid_data)?)
+    }
+
+    /// Saves if `auto_save_interval` passed since the last save
+    pub(crate) fn auto_save(&mut self, ctx: &egui::Context) {
+        if self
+            .auto_save_interval
+            .is_some_and(|interval| self.last_save.elapsed() >= interval)
+        {
+            if let Err(e) = self.save(ctx) {
+                log::error!("Couldn't save gui state: {}", e);
+            }
+        }
+    }
+}
+
+/// The key the main window's geometry is stored under in `Storage`
+pub const WINDOW_GEOMETRY_KEY: &str = "window_geometry";
+
+/// The position, size and state of a window, `run` stores this for the main window and restores
+/// it on the next start
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    /// Outer position in physical pixels, `None` if the platform doesn't report it
+    pub position: Option<(i32, i32)>,
+    /// Inner size in physical pixels
+    pub size: (u32, u32),
+    pub maximized: bool,
+    pub fullscreen: bool,
+}
+
+impl WindowGeometry {
+    /// The smallest size `sanitized` restores a window with, so a bad saved size can't make the
+    /// window effectively invisible
+    pub const MIN_SIZE: (u32, u32) = (160, 120);
+
+    /// Returns the current geometry of `window`
+    pub fn from_window(window: &Window) -> WindowGeometry {
+        let size = window.inner_size();
+        WindowGeometry {
+            position: window.outer_position().ok().map(|pos| (pos.x, pos.y)),
+            size: (size.width, size.height),
+            maximized: window.is_maximized(),
+            fullscreen: window.fullscreen().is_some(),
+        }
+    }
+
+    /// Update from `window` after it was moved or resized. The position and size are only taken
+    /// while the window isn't maximized or fullscreen, so restoring it later returns it to where
+    /// it was before. Nothing is taken while the window is minimized, Windows reports a size of
+    /// zero and a position far off screen then.
+    pub fn update(&mut self, window: &Window) {
+        let size = window.inner_size();
+        if window.is_minimized() == Some(true) || size.width == 0 || size.height == 0 {
+            return;
+        }
+        self.maximized = window.is_maximized();
+        self.fullscreen = window.fullscreen().is_some();
+        if !self.maximized && !self.fullscreen {
+            self.size = (size.width, size.height);
+            if let Ok(pos) = window.outer_position() {
+                self.position = Some((pos.x, pos.y));
+            }
+        }
+    }
+
+    /// Returns the geometry adjusted for the monitors given as (position, size) in physical
+    /// pixels: the size is grown to at least `MIN_SIZE` and shrunk to fit the largest monitor,
+    /// and the position is dropped if the top of the window wouldn't be visible on any monitor,
+    /// e.g. because the monitor it was on got disconnected. With no monitors only the minimum
+    /// size is applied.
+    pub fn sanitized(
+        &self,
+        monitors: &[(PhysicalPosition<i32>, PhysicalSize<u32>)],
+    ) -> WindowGeometry {
+        let mut geometry = self.clone();
+        geometry.size = (
+            geometry.size.0.max(Self::MIN_SIZE.0),
+            geometry.size.1.max(Self::MIN_SIZE.1),
+        );
+        if monitors.is_empty() {
+            return geometry;
+        }
+
+        let max_width = monitors
+            .iter()
+            .map(|(_, size)| size.width)
+            .max()
+            .unwrap_or(0);
+        let max_height = monitors
+            .iter()
+            .map(|(_, size)| size.height)
+            .max()
+            .unwrap_or(0);
+        geometry.size = (
+            geometry.size.0.min(max_width.max(1)),
+            geometry.size.1.min(max_height.max(1)),
+        );
+
+        if let Some((x, y)) = geometry.position {
+            // The strip along the top of the window has to be visible so it can be dragged
+            let (width, height) = (geometry.size.0 as i64, 32);
+            let visible = monitors.iter().any(|(pos, size)| {
+                let overlap_x = (x as i64 + width).min(pos.x as i64 + size.width as i64)
+                    - (x as i64).max(pos.x as i64);
+                let overlap_y = (y as i64 + height).min(pos.y as i64 + size.height as i64)
+                    - (y as i64).max(pos.y as i64);
+                overlap_x >= width.min(64) && overlap_y >= 16
+            });
+            if !visible {
+                geometry.position = None;
+            }
+        }
+        geometry
+    }
+
+    /// Apply the geometry to a window that is about to be built, after making sure it fits the
+    /// currently connected monitors with `sanitized`
+    pub fn apply<T>(
+        &self,
+        wb: WindowBuilder,
+        event_loop: &EventLoopWindowTarget<T>,
+    ) -> WindowBuilder {
+        let monitors: Vec<_> = event_loop
+            .available_monitors()
+            .map(|monitor| (monitor.position(), monitor.size()))
+            .collect();
+        let geometry = self.sanitized(&monitors);
+
+        let mut wb = wb
+            .with_inner_size(PhysicalSize::new(geometry.size.0, geometry.size.1))
+            .with_maximized(geometry.maximized);
+        if let Some((x, y)) = geometry.position {
+            wb = wb.with_position(PhysicalPosition::new(x, y));
+        }
+        if geometry.fullscreen {
+            wb = wb.with_fullscreen(Some(Fullscreen::Borderless(None)));
+        }
+        wb
+    }
+}
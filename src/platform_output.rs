@@ -0,0 +1,126 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use egui::{output::OpenUrl, CursorIcon, PlatformOutput};
+
+/// Implement this to intercept what the gui asks the platform to do, see
+/// `EguiManager::set_platform_output_handler`. Each function returns if it handled the request,
+/// requests that weren't handled are applied to the window as usual.
+pub trait PlatformOutputHandler {
+    /// Called when the gui copies text to the clipboard
+    fn copy_text(&mut self, _text: &str) -> bool {
+        false
+    }
+
+    /// Called when the gui wants to open a url, e.g. from a `Hyperlink`
+    fn open_url(&mut self, _url: &OpenUrl) -> bool {
+        false
+    }
+
+    /// Called when the cursor icon the gui wants changes
+    fn set_cursor_icon(&mut self, _icon: CursorIcon) -> bool {
+        false
+    }
+}
+
+/// Passes platform output to a `PlatformOutputHandler` and removes what it handled, so the rest
+/// can be applied to the window. `EguiManager` does this for you, use this directly to test gui
+/// code against an `egui::Context` without a window.
+pub struct PlatformOutputFilter {
+    handler: Box<dyn PlatformOutputHandler>,
+    last_cursor_icon: Option<CursorIcon>,
+    window_cursor_icon: CursorIcon,
+}
+
+impl PlatformOutputFilter {
+    pub fn new(handler: Box<dyn PlatformOutputHandler>) -> PlatformOutputFilter {
+        PlatformOutputFilter {
+            handler,
+            last_cursor_icon: None,
+            window_cursor_icon: CursorIcon::Default,
+        }
+    }
+
+    /// Returns the output with everything the handler handled removed
+    pub fn filter(&mut self, mut output: PlatformOutput) -> PlatformOutput {
+        if !output.copied_text.is_empty() && self.handler.copy_text(&output.copied_text) {
+            output.copied_text.clear();
+        }
+        if let Some(url) = &output.open_url {
+            if self.handler.open_url(url) {
+                output.open_url = None;
+            }
+        }
+
+        if self.last_cursor_icon != Some(output.cursor_icon) {
+            self.last_cursor_icon = Some(output.cursor_icon);
+            if !self.handler.set_cursor_icon(output.cursor_icon) {
+                self.window_cursor_icon = output.cursor_icon;
+            }
+        }
+        // A handled icon can't be removed from the output, so the window keeps the last icon
+        // that wasn't handled instead
+        output.cursor_icon = self.window_cursor_icon;
+
+        output
+    }
+}
+
+#[derive(Default)]
+struct Recorded {
+    copied_text: Vec<String>,
+    opened_urls: Vec<String>,
+    cursor_icons: Vec<CursorIcon>,
+}
+
+/// A `PlatformOutputHandler` that handles everything by recording it, so gui code can be tested
+/// without touching the real clipboard, browser or cursor. Clones share the recording, so keep a
+/// clone to inspect it after passing one to `EguiManager::set_platform_output_handler` or a
+/// `PlatformOutputFilter`.
+#[derive(Clone, Default)]
+pub struct RecordingHandler {
+    recorded: Rc<RefCell<Recorded>>,
+}
+
+impl RecordingHandler {
+    pub fn new() -> RecordingHandler {
+        RecordingHandler::default()
+    }
+
+    /// Returns all text copied to the clipboard, oldest first
+    pub fn copied_text(&self) -> Vec<String> {
+        self.recorded.borrow().copied_text.clone()
+    }
+
+    /// Returns all urls that were opened, oldest first
+    pub fn opened_urls(&self) -> Vec<String> {
+        self.recorded.borrow().opened_urls.clone()
+    }
+
+    /// Returns every cursor icon the gui changed to, oldest first
+    pub fn cursor_icons(&self) -> Vec<CursorIcon> {
+        self.recorded.borrow().cursor_icons.clone()
+    }
+
+    /// Forget everything recorded so far
+    pub fn clear(&self) {
+        *self.recorded.borrow_mut() = Recorded::default();
+    }
+}
+
+impl PlatformOutputHandler for RecordingHandler {
+    fn copy_text(&mut self, text: &str) -> bool {
+        self.recorded.borrow_mut().copied_text.push(text.to_owned());
+        true
+    }
+
+    fn open_url(&mut self, url: &OpenUrl) -> bool {
+        self.recorded.borrow_mut().opened_urls.push(url.url.clone());
+        true
+    }
+
+    fn set_cursor_icon(&mut self, icon: CursorIcon) -> bool {
+        self.recorded.borrow_mut().cursor_icons.push(icon);
+        true
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{
+        DeviceEvent, DeviceId, ElementState, Event, Force, Ime, KeyboardInput, ModifiersState,
+        MouseButton, MouseScrollDelta, Touch, TouchPhase, VirtualKeyCode, WindowEvent,
+    },
+    window::WindowId,
+};
+
+use crate::io::{keyboard::Keyboard, mouse::Mouse};
+use crate::Timer;
+
+/// The subset of winit events that is recorded: keyboard, text, IME, mouse and touch input, plus
+/// resizes, scale factor changes and focus. Other window events such as moves, theme changes,
+/// file drops and touchpad gestures are not recorded and so can't be reproduced by a replay.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RecordedEvent {
+    Key {
+        scancode: u32,
+        key: Option<VirtualKeyCode>,
+        pressed: bool,
+    },
+    Modifiers(ModifiersState),
+    Character(char),
+    Ime(Ime),
+    CursorMoved {
+        x: f64,
+        y: f64,
+    },
+    CursorEntered,
+    CursorLeft,
+    MouseButton {
+        button: MouseButton,
+        pressed: bool,
+    },
+    LineScroll {
+        x: f32,
+        y: f32,
+    },
+    PixelScroll {
+        x: f64,
+        y: f64,
+    },
+    MouseMotion {
+        x: f64,
+        y: f64,
+    },
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        x: f64,
+        y: f64,
+        /// Normalized pressure from 0 to 1
+        force: Option<f64>,
+    },
+    Resized {
+        width: u32,
+        height: u32,
+    },
+    ScaleFactorChanged {
+        scale_factor: f64,
+        width: u32,
+        height: u32,
+    },
+    Focused(bool),
+}
+
+fn state(pressed: bool) -> ElementState {
+    if pressed {
+        ElementState::Pressed
+    } else {
+        ElementState::Released
+    }
+}
+
+impl RecordedEvent {
+    /// Converts a winit event, returns `None` for events that aren't recorded
+    pub fn from_event(event: &Event<()>) -> Option<RecordedEvent> {
+        Some(match event {
+            Event::WindowEvent { event, .. } => match event {
+                WindowEvent::KeyboardInput { input, .. } => RecordedEvent::Key {
+                    scancode: input.scancode,
+                    key: input.virtual_keycode,
+                    pressed: input.state == ElementState::Pressed,
+                },
+                WindowEvent::ModifiersChanged(modifiers) => RecordedEvent::Modifiers(*modifiers),
+                WindowEvent::ReceivedCharacter(c) => RecordedEvent::Character(*c),
+                WindowEvent::Ime(ime) => RecordedEvent::Ime(ime.clone()),
+                WindowEvent::CursorMoved { position, .. } => RecordedEvent::CursorMoved {
+                    x: position.x,
+                    y: position.y,
+                },
+                WindowEvent::CursorEntered { .. } => RecordedEvent::CursorEntered,
+                WindowEvent::CursorLeft { .. } => RecordedEvent::CursorLeft,
+                WindowEvent::MouseInput { state, button, .. } => RecordedEvent::MouseButton {
+                    button: *button,
+                    pressed: *state == ElementState::Pressed,
+                },
+                WindowEvent::MouseWheel {
+                    delta: MouseScrollDelta::LineDelta(x, y),
+                    ..
+                } => RecordedEvent::LineScroll { x: *x, y: *y },
+                WindowEvent::MouseWheel {
+                    delta: MouseScrollDelta::PixelDelta(pos),
+                    ..
+                } => RecordedEvent::PixelScroll { x: pos.x, y: pos.y },
+                WindowEvent::Touch(touch) => RecordedEvent::Touch {
+                    id: touch.id,
+                    phase: touch.phase,
+                    x: touch.location.x,
+                    y: touch.location.y,
+                    force: touch.force.map(|force| force.normalized()),
+                },
+                WindowEvent::Resized(size) => RecordedEvent::Resized {
+                    width: size.width,
+                    height: size.height,
+                },
+                WindowEvent::ScaleFactorChanged {
+                    scale_factor,
+                    new_inner_size,
+                } => RecordedEvent::ScaleFactorChanged {
+                    scale_factor: *scale_factor,
+                    width: new_inner_size.width,
+                    height: new_inner_size.height,
+                },
+                WindowEvent::Focused(focused) => RecordedEvent::Focused(*focused),
+                _ => return None,
+            },
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta },
+                ..
+            } => RecordedEvent::MouseMotion {
+                x: delta.0,
+                y: delta.1,
+            },
+            _ => return None,
+        })
+    }
+
+    /// Converts back into a winit event that can be passed to `Keyboard`, `Mouse` or
+    /// `Context::handle_event`
+    #[allow(deprecated)]
+    pub fn to_event(&self) -> Event<'static, ()> {
+        // Safety: the dummy ids are only ever compared by our own event handlers, they are never
+        // passed into winit
+        let device_id = unsafe { DeviceId::dummy() };
+        let window_event = |event| Event::WindowEvent {
+            window_id: unsafe { WindowId::dummy() },
+            event,
+        };
+
+        match *self {
+            RecordedEvent::Key {
+                scancode,
+                key,
+                pressed,
+            } => window_event(WindowEvent::KeyboardInput {
+                device_id,
+                input: KeyboardInput {
+                    scancode,
+                    state: state(pressed),
+                    virtual_keycode: key,
+                    modifiers: ModifiersState::empty(),
+                },
+                is_synthetic: false,
+            }),
+            RecordedEvent::Modifiers(modifiers) => {
+                window_event(WindowEvent::ModifiersChanged(modifiers))
+            }
+            RecordedEvent::Character(c) => window_event(WindowEvent::ReceivedCharacter(c)),
+            RecordedEvent::Ime(ref ime) => window_event(WindowEvent::Ime(ime.clone())),
+            RecordedEvent::CursorMoved { x, y } => window_event(WindowEvent::CursorMoved {
+                device_id,
+                position: PhysicalPosition::new(x, y),
+                modifiers: ModifiersState::empty(),
+            }),
+            RecordedEvent::CursorEntered => window_event(WindowEvent::CursorEntered { device_id }),
+            RecordedEvent::CursorLeft => window_event(WindowEvent::CursorLeft { device_id }),
+            RecordedEvent::MouseButton { button, pressed } => {
+                window_event(WindowEvent::MouseInput {
+                    device_id,
+                    state: state(pressed),
+                    button,
+                    modifiers: ModifiersState::empty(),
+                })
+            }
+            RecordedEvent::LineScroll { x, y } => window_event(WindowEvent::MouseWheel {
+                device_id,
+                delta: MouseScrollDelta::LineDelta(x, y),
+                phase: TouchPhase::Moved,
+                modifiers: ModifiersState::empty(),
+            }),
+            RecordedEvent::PixelScroll { x, y } => window_event(WindowEvent::MouseWheel {
+                device_id,
+                delta: MouseScrollDelta::PixelDelta(PhysicalPosition::new(x, y)),
+                phase: TouchPhase::Moved,
+                modifiers: ModifiersState::empty(),
+            }),
+            RecordedEvent::MouseMotion { x, y } => Event::DeviceEvent {
+                device_id,
+                event: DeviceEvent::MouseMotion { delta: (x, y) },
+            },
+            RecordedEvent::Touch {
+                id,
+                phase,
+                x,
+                y,
+                force,
+            } => window_event(WindowEvent::Touch(Touch {
+                device_id,
+                phase,
+                location: PhysicalPosition::new(x, y),
+                force: force.map(Force::Normalized),
+                id,
+            })),
+            RecordedEvent::Resized { width, height } => {
+                window_event(WindowEvent::Resized(PhysicalSize::new(width, height)))
+            }
+            RecordedEvent::ScaleFactorChanged {
+                scale_factor,
+                width,
+                height,
+            } => window_event(WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                // The event borrows the size mutably so the app can change it, a replayed event
+                // needs a 'static one. Scale factor changes are rare so leaking it is fine.
+                new_inner_size: Box::leak(Box::new(PhysicalSize::new(width, height))),
+            }),
+            RecordedEvent::Focused(focused) => window_event(WindowEvent::Focused(focused)),
+        }
+    }
+}
+
+/// A single line of a recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Record {
+    Event(RecordedEvent),
+    /// The end of a frame's events, `delta` is what `Timer::go` returned for that frame
+    Frame {
+        delta: f32,
+    },
+}
+
+/// Writes input to a recording, see `Context::start_recording`. Recordings are stored as one JSON
+/// `Record` per line.
+pub struct InputRecorder {
+    writer: Box<dyn Write>,
+}
+
+impl InputRecorder {
+    /// Create a recorder writing to a new file at `path`, replacing it if it exists
+    pub fn create(path: impl AsRef<Path>) -> io::Result<InputRecorder> {
+        Ok(InputRecorder::new(BufWriter::new(File::create(path)?)))
+    }
+
+    /// Create a recorder writing to any writer
+    pub fn new(writer: impl Write + 'static) -> InputRecorder {
+        InputRecorder {
+            writer: Box::new(writer),
+        }
+    }
+
+    fn write(&mut self, record: &Record) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.writer.write_all(b"\n")
+    }
+
+    /// Record an event if it's one of the recorded kinds
+    pub fn record_event(&mut self, event: &Event<()>) -> io::Result<()> {
+        match RecordedEvent::from_event(event) {
+            Some(event) => self.write(&Record::Event(event)),
+            None => Ok(()),
+        }
+    }
+
+    /// Record the end of a frame. The recording is flushed after every frame so it survives the
+    /// app panicking or being killed.
+    pub fn record_frame(&mut self, delta: f32) -> io::Result<()> {
+        self.write(&Record::Frame { delta })?;
+        self.writer.flush()
+    }
+
+    /// Write any buffered records out
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// The input of one recorded frame
+pub struct ReplayFrame {
+    pub events: Vec<Event<'static, ()>>,
+    pub delta: f32,
+}
+
+impl ReplayFrame {
+    /// Feeds this frame's events to the keyboard and mouse and advances them and the timer by the
+    /// recorded delta, the same way the application loop does before calling `Application::update`.
+    /// Call `next_frame` on the keyboard and mouse once you're done with the frame. Touch input is
+    /// only replayed by passing `events` to `Context::handle_event` or `TestContext::handle_event`.
+    pub fn apply(&self, keyboard: &mut Keyboard, mouse: &mut Mouse, timer: &mut Timer) {
+        for event in &self.events {
+            keyboard.handle_event(event);
+            mouse.handle_event(event);
+        }
+        timer.advance(self.delta);
+        keyboard.advance_time(self.delta);
+        mouse.advance_time(self.delta);
+    }
+}
+
+/// Reads a recording back frame by frame
+pub struct InputReplay {
+    records: VecDeque<Record>,
+}
+
+impl InputReplay {
+    /// Load the recording at `path`
+    pub fn load(path: impl AsRef<Path>) -> io::Result<InputReplay> {
+        InputReplay::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Read a recording from any reader
+    pub fn from_reader(reader: impl BufRead) -> io::Result<InputReplay> {
+        let mut records = VecDeque::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            records.push_back(serde_json::from_str(&line)?);
+        }
+        Ok(InputReplay { records })
+    }
+
+    /// Returns the next recorded frame, or `None` once the recording is over. Events recorded
+    /// after the last frame are dropped.
+    pub fn next_frame(&mut self) -> Option<ReplayFrame> {
+        let mut events = Vec::new();
+        while let Some(record) = self.records.pop_front() {
+            match record {
+                Record::Event(event) => events.push(event.to_event()),
+                Record::Frame { delta } => return Some(ReplayFrame { events, delta }),
+            }
+        }
+        None
+    }
+}
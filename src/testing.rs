@@ -0,0 +1,65 @@
+use crate::io::{keyboard::Keyboard, mouse::Mouse, touch::Touch};
+use crate::Timer;
+
+/// The input devices and timer of a `Context` without a window or GPU, so input handling can be
+/// unit tested. Feed it input through `handle_event` or the devices' `inject_*` functions, then
+/// call `advance` and `next_frame` around your per-frame logic like the application loop does:
+///
+/// ```
+/// use wgpu_app::testing::TestContext;
+/// use winit::event::VirtualKeyCode;
+///
+/// let mut ctx = TestContext::new();
+/// ctx.keyboard.inject_press(VirtualKeyCode::Space);
+/// ctx.advance(1.0 / 60.0);
+/// assert!(ctx.keyboard.pressed_this_frame(&VirtualKeyCode::Space));
+/// ctx.next_frame();
+/// assert!(!ctx.keyboard.pressed_this_frame(&VirtualKeyCode::Space));
+/// ```
+pub struct TestContext {
+    pub keyboard: Keyboard,
+    pub mouse: Mouse,
+    pub touch: Touch,
+    pub timer: Timer,
+}
+
+impl Default for TestContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestContext {
+    pub fn new() -> TestContext {
+        TestContext {
+            keyboard: Keyboard::new(),
+            mouse: Mouse::new(),
+            touch: Touch::new(),
+            timer: Timer::new(),
+        }
+    }
+
+    /// Pass a winit event to all input devices, like `Context::handle_event`
+    pub fn handle_event(&mut self, event: &winit::event::Event<()>) {
+        self.keyboard.handle_event(event);
+        self.mouse.handle_event(event);
+        self.touch.handle_event_with_mouse(event, &mut self.mouse);
+    }
+
+    /// Start a frame that took `dt` seconds, this is what the application loop does right before
+    /// calling `Application::update`
+    pub fn advance(&mut self, dt: f32) {
+        self.timer.advance(dt);
+        self.keyboard.advance_time(dt);
+        self.mouse.advance_time(dt);
+        self.touch.advance_time(dt);
+    }
+
+    /// End the current frame, this is what the application loop does right after calling
+    /// `Application::update`
+    pub fn next_frame(&mut self) {
+        self.mouse.next_frame();
+        self.keyboard.next_frame();
+        self.touch.next_frame();
+    }
+}
@@ -12,6 +12,12 @@ pub struct Timer {
     abs_time: f32,
 }
 
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Keeps track of timing
 impl Timer {
     pub fn new() -> Timer {
@@ -45,6 +51,13 @@ impl Timer {
             return None;
         }
 
+        self.advance(delta);
+        Some(delta)
+    }
+
+    /// Advance the timer by `delta` seconds as if `go` had returned it, regardless of how much
+    /// time actually passed. Useful to drive the timer deterministically, e.g. when replaying input.
+    pub fn advance(&mut self, delta: f32) {
         self.abs_time += self.last_delta;
 
         self.frame_count += 1;
@@ -57,7 +70,6 @@ impl Timer {
 
         self.last_delta = delta;
         self.last = Instant::now();
-        Some(delta)
     }
 
     /// Set how many seconds should pass before the next tick
@@ -1 +1,3 @@
-pub mod persistent_window;
\ No newline at end of file
+pub mod paint_callback;
+pub mod persistent_window;
+pub mod viewport;
\ No newline at end of file
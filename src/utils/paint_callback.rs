@@ -0,0 +1,51 @@
+use egui_wgpu::CallbackFn;
+
+/// Create an `egui::PaintCallback` that draws with wgpu into `rect` as part of the Egui render
+/// pass, add it to a `Ui` with `ui.painter().add(callback)`.
+///
+/// Both stages get the resource of type `T` that was stored with
+/// `EguiManager::insert_paint_resource`, typically a struct holding your pipelines and buffers.
+/// `prepare` runs before the render pass and can update buffers or record commands into Egui's
+/// encoder, returning any extra command buffers. `paint` records draw calls into the render pass,
+/// which has its viewport and scissor rect set to `rect`. If no resource of type `T` is stored the
+/// callback draws nothing.
+pub fn paint_callback<T: Send + Sync + 'static>(
+    rect: egui::Rect,
+    prepare: impl Fn(
+            &wgpu::Device,
+            &wgpu::Queue,
+            &mut wgpu::CommandEncoder,
+            &mut T,
+        ) -> Vec<wgpu::CommandBuffer>
+        + Send
+        + Sync
+        + 'static,
+    paint: impl for<'a, 'b> Fn(egui::PaintCallbackInfo, &'a mut wgpu::RenderPass<'b>, &'b T)
+        + Send
+        + Sync
+        + 'static,
+) -> egui::PaintCallback {
+    let callback = CallbackFn::new()
+        .prepare(
+            move |device, queue, encoder, resources| match resources.get_mut::<T>() {
+                Some(resource) => prepare(device, queue, encoder, resource),
+                None => {
+                    log::warn!(
+                        "No paint resource of type {} stored, skipping paint callback",
+                        std::any::type_name::<T>()
+                    );
+                    Vec::new()
+                }
+            },
+        )
+        .paint(move |info, render_pass, resources| {
+            if let Some(resource) = resources.get::<T>() {
+                paint(info, render_pass, resource);
+            }
+        });
+
+    egui::PaintCallback {
+        rect,
+        callback: std::sync::Arc::new(callback),
+    }
+}
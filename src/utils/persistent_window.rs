@@ -43,6 +43,12 @@ impl<S> PersistentWindow<S> {
     }
 }
 
+impl<S> Default for PersistentWindowManager<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<S> PersistentWindowManager<S> {
     /// Create a new `PersistentWindowManager`
     pub fn new() -> PersistentWindowManager<S> {
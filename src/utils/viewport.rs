@@ -0,0 +1,348 @@
+use std::f32::consts::FRAC_PI_2;
+
+use wgpu::TextureFormat;
+
+use crate::context::{EguiManager, WgpuState};
+
+/// A camera that orbits around a target point, controlled by dragging and scrolling in a
+/// `Viewport`. Uses a right-handed coordinate system with y up, matrices are column major and
+/// map depth to wgpu's 0..1 range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrbitCamera {
+    /// The point the camera looks at and orbits around
+    pub target: [f32; 3],
+    /// Distance from the camera to the target
+    pub distance: f32,
+    /// Rotation around the y axis in radians, 0 looks along -z
+    pub yaw: f32,
+    /// Rotation above the horizon in radians, limited to just under straight up and down
+    pub pitch: f32,
+    /// Vertical field of view in radians
+    pub fov_y: f32,
+    pub near: f32,
+    pub far: f32,
+
+    /// Radians rotated per pixel dragged
+    pub orbit_sensitivity: f32,
+    /// Factor the distance is multiplied by per line scrolled towards the target
+    pub zoom_factor: f32,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        OrbitCamera {
+            target: [0.0; 3],
+            distance: 5.0,
+            yaw: 0.0,
+            pitch: 0.3,
+            fov_y: 45f32.to_radians(),
+            near: 0.1,
+            far: 1000.0,
+
+            orbit_sensitivity: 0.01,
+            zoom_factor: 0.9,
+        }
+    }
+}
+
+impl OrbitCamera {
+    /// Returns the position of the camera
+    pub fn eye(&self) -> [f32; 3] {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        add(
+            self.target,
+            scale(
+                [cos_pitch * sin_yaw, sin_pitch, cos_pitch * cos_yaw],
+                self.distance,
+            ),
+        )
+    }
+
+    /// Returns the camera's right, up and forward directions
+    fn axes(&self) -> ([f32; 3], [f32; 3], [f32; 3]) {
+        let forward = normalize(sub(self.target, self.eye()));
+        let right = normalize(cross(forward, [0.0, 1.0, 0.0]));
+        let up = cross(right, forward);
+        (right, up, forward)
+    }
+
+    /// Rotate around the target by a drag of `dx`, `dy` pixels
+    pub fn orbit(&mut self, dx: f32, dy: f32) {
+        self.yaw -= dx * self.orbit_sensitivity;
+        self.pitch =
+            (self.pitch + dy * self.orbit_sensitivity).clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+    }
+
+    /// Move the target by a drag of `dx`, `dy` pixels in a viewport `viewport_height` pixels high,
+    /// so the point under the cursor stays under the cursor at the target's depth
+    pub fn pan(&mut self, dx: f32, dy: f32, viewport_height: f32) {
+        let units_per_pixel =
+            2.0 * self.distance * (self.fov_y / 2.0).tan() / viewport_height.max(1.0);
+        let (right, up, _) = self.axes();
+        self.target = add(
+            self.target,
+            add(
+                scale(right, -dx * units_per_pixel),
+                scale(up, dy * units_per_pixel),
+            ),
+        );
+    }
+
+    /// Move towards the target by `lines` scrolled, negative values move away from it
+    pub fn zoom(&mut self, lines: f32) {
+        self.distance = (self.distance * self.zoom_factor.powf(lines)).max(self.near);
+    }
+
+    /// Returns the matrix transforming world space to view space
+    pub fn view_matrix(&self) -> [[f32; 4]; 4] {
+        let eye = self.eye();
+        let (s, u, f) = self.axes();
+        [
+            [s[0], u[0], -f[0], 0.0],
+            [s[1], u[1], -f[1], 0.0],
+            [s[2], u[2], -f[2], 0.0],
+            [-dot(s, eye), -dot(u, eye), dot(f, eye), 1.0],
+        ]
+    }
+
+    /// Returns the perspective projection matrix for a viewport with this aspect ratio (width / height)
+    pub fn projection_matrix(&self, aspect: f32) -> [[f32; 4]; 4] {
+        let h = 1.0 / (self.fov_y / 2.0).tan();
+        let w = h / aspect;
+        let r = self.far / (self.near - self.far);
+        [
+            [w, 0.0, 0.0, 0.0],
+            [0.0, h, 0.0, 0.0],
+            [0.0, 0.0, r, -1.0],
+            [0.0, 0.0, r * self.near, 0.0],
+        ]
+    }
+
+    /// Returns `projection_matrix * view_matrix`, ready to be uploaded to a uniform buffer
+    pub fn view_projection(&self, aspect: f32) -> [[f32; 4]; 4] {
+        let (p, v) = (self.projection_matrix(aspect), self.view_matrix());
+        let mut out = [[0.0; 4]; 4];
+        for (c, column) in out.iter_mut().enumerate() {
+            for (r, value) in column.iter_mut().enumerate() {
+                *value = (0..4).map(|k| p[k][r] * v[c][k]).sum();
+            }
+        }
+        out
+    }
+}
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: [f32; 3], s: f32) -> [f32; 3] {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(a: [f32; 3]) -> [f32; 3] {
+    scale(a, 1.0 / dot(a, a).sqrt())
+}
+
+/// An Egui widget showing a 3D scene. The scene is rendered to an offscreen color and depth target
+/// that is resized to fit the space the widget takes up, and an `OrbitCamera` is controlled with
+/// the mouse while the pointer is over the widget: drag with the left button to orbit, with the
+/// right or middle button to pan and scroll to zoom.
+///
+/// Each frame, call `show` while building the gui, then `update_target` and render the scene
+/// with `begin_render_pass` before painting the gui:
+/// ```ignore
+/// ctx.egui.begin_frame(&ctx.wgpu_state);
+/// egui::CentralPanel::default().show(&ctx.egui.ctx.clone(), |ui| viewport.show(ui));
+/// let frame = ctx.egui.end_frame(&ctx.wgpu_state);
+///
+/// viewport.update_target(&mut ctx.egui, &ctx.wgpu_state.device);
+/// let view_projection = viewport.camera.view_projection(viewport.aspect());
+/// // write view_projection to your uniform buffer
+/// {
+///     let mut pass = viewport.begin_render_pass(&ctx.egui, &mut encoder, wgpu::Color::BLACK);
+///     // draw the scene
+/// }
+/// let user_cmd_bufs = ctx.egui.paint(&ctx.wgpu_state.device, &ctx.wgpu_state.queue, &mut encoder, &view, frame);
+/// ```
+pub struct Viewport {
+    pub camera: OrbitCamera,
+
+    color: egui::TextureId,
+    depth: wgpu::Texture,
+    depth_view: wgpu::TextureView,
+    size: [u32; 2],
+    wanted_size: [u32; 2],
+}
+
+impl Viewport {
+    /// The format of the depth target
+    pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+
+    /// Create a viewport whose color target has the given format, pipelines drawing into it must
+    /// use this format and `DEPTH_FORMAT`
+    pub fn new(egui: &mut EguiManager, wgpu_state: &WgpuState, format: TextureFormat) -> Viewport {
+        let size = [1, 1];
+        let color =
+            egui.create_render_target(wgpu_state, Some(size), format, wgpu::FilterMode::Linear);
+        let (depth, depth_view) = create_depth_texture(&wgpu_state.device, size);
+        Viewport {
+            camera: OrbitCamera::default(),
+
+            color,
+            depth,
+            depth_view,
+            size,
+            wanted_size: size,
+        }
+    }
+
+    /// Show the scene in all the available space of `ui` and move the camera with the mouse if
+    /// the pointer is over it. The camera is driven by Egui's input, so it keeps working while
+    /// `Context::filter_gui_captured_input` is set.
+    pub fn show(&mut self, ui: &mut egui::Ui) -> egui::Response {
+        let (rect, response) =
+            ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+        let pixels_per_point = ui.ctx().pixels_per_point();
+        self.wanted_size = [
+            (rect.width() * pixels_per_point).round().max(1.0) as u32,
+            (rect.height() * pixels_per_point).round().max(1.0) as u32,
+        ];
+
+        ui.painter().image(
+            self.color,
+            rect,
+            egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+            egui::Color32::WHITE,
+        );
+
+        let delta = response.drag_delta() * pixels_per_point;
+        let (dx, dy) = (delta.x, delta.y);
+        if response.dragged_by(egui::PointerButton::Primary) {
+            self.camera.orbit(dx, dy);
+        } else if response.dragged_by(egui::PointerButton::Secondary)
+            || response.dragged_by(egui::PointerButton::Middle)
+        {
+            self.camera.pan(dx, dy, self.wanted_size[1] as f32);
+        }
+        if response.hovered() {
+            // egui-winit reports a scrolled line as 50 points
+            self.camera.zoom(ui.input(|i| i.scroll_delta.y) / 50.0);
+        }
+
+        response
+    }
+
+    /// Recreate the color and depth targets if the widget's size changed since they were created,
+    /// call this after `show` and before rendering. Returns if the targets were recreated.
+    pub fn update_target(&mut self, egui: &mut EguiManager, device: &wgpu::Device) -> bool {
+        if self.wanted_size == self.size {
+            return false;
+        }
+        self.size = self.wanted_size;
+        egui.resize_render_target(device, self.color, self.size);
+        (self.depth, self.depth_view) = create_depth_texture(device, self.size);
+        true
+    }
+
+    /// Begin a render pass drawing to the viewport's targets, clearing color to `clear_color` and
+    /// depth to 1
+    pub fn begin_render_pass<'a>(
+        &'a self,
+        egui: &'a EguiManager,
+        encoder: &'a mut wgpu::CommandEncoder,
+        clear_color: wgpu::Color,
+    ) -> wgpu::RenderPass<'a> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Viewport render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: self.color_view(egui),
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(clear_color),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        })
+    }
+
+    /// Returns the view of the color target
+    ///
+    /// # Panics:
+    /// If the color target was freed from `egui`
+    pub fn color_view<'a>(&self, egui: &'a EguiManager) -> &'a wgpu::TextureView {
+        egui.render_target(self.color)
+            .expect("Viewport color target was freed")
+    }
+
+    /// Returns the view of the depth target
+    pub fn depth_view(&self) -> &wgpu::TextureView {
+        &self.depth_view
+    }
+
+    /// Returns the id of the color target, to show it somewhere else in the gui as well
+    pub fn texture_id(&self) -> egui::TextureId {
+        self.color
+    }
+
+    /// Returns the size of the targets in pixels
+    pub fn size(&self) -> [u32; 2] {
+        self.size
+    }
+
+    /// Returns the aspect ratio (width / height) of the targets
+    pub fn aspect(&self) -> f32 {
+        self.size[0] as f32 / self.size[1] as f32
+    }
+
+    /// Free the color target, the viewport can't be used anymore afterwards
+    pub fn free(self, egui: &mut EguiManager) {
+        egui.free_texture(self.color);
+    }
+}
+
+fn create_depth_texture(
+    device: &wgpu::Device,
+    size: [u32; 2],
+) -> (wgpu::Texture, wgpu::TextureView) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Viewport depth target"),
+        size: wgpu::Extent3d {
+            width: size[0],
+            height: size[1],
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: Viewport::DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
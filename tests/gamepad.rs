@@ -0,0 +1,61 @@
+#![cfg(feature = "gamepad")]
+
+use wgpu_app::io::gamepad::{GamepadAxis, GamepadButton, GamepadEvent, Gamepads, MockBackend};
+
+fn connected() -> (MockBackend, Gamepads) {
+    let backend = MockBackend::new();
+    let mut gamepads = Gamepads::with_backend(Box::new(backend.clone()));
+    backend.push(GamepadEvent::Connected(1, "Mock pad".to_string()));
+    gamepads.poll();
+    (backend, gamepads)
+}
+
+#[test]
+fn connect_and_disconnect_are_reported_per_frame() {
+    let (backend, mut gamepads) = connected();
+
+    assert_eq!(gamepads.connected_this_frame().collect::<Vec<_>>(), [1]);
+    assert!(gamepads.get(0).is_none());
+    assert_eq!(gamepads.get(1).map(|pad| pad.name()), Some("Mock pad"));
+
+    gamepads.next_frame();
+    backend.push(GamepadEvent::Disconnected(1));
+    gamepads.poll();
+
+    assert_eq!(gamepads.disconnected_this_frame().collect::<Vec<_>>(), [1]);
+    assert!(gamepads.get(1).is_none());
+}
+
+#[test]
+fn button_tapped_within_one_frame_reports_press_and_release() {
+    let (backend, mut gamepads) = connected();
+    gamepads.next_frame();
+    backend.push(GamepadEvent::ButtonPressed(1, GamepadButton::South));
+    backend.push(GamepadEvent::ButtonReleased(1, GamepadButton::South));
+    gamepads.poll();
+
+    let pad = gamepads.get(1).unwrap();
+    assert!(pad.pressed_this_frame(GamepadButton::South));
+    assert!(pad.released_this_frame(GamepadButton::South));
+    assert!(!pad.is_pressed(GamepadButton::South));
+
+    gamepads.next_frame();
+    assert!(!gamepads
+        .get(1)
+        .unwrap()
+        .pressed_this_frame(GamepadButton::South));
+}
+
+#[test]
+fn stick_deadzone_is_radial_and_rescaled() {
+    let (backend, mut gamepads) = connected();
+    backend.push(GamepadEvent::AxisChanged(1, GamepadAxis::LeftStickX, 0.1));
+    backend.push(GamepadEvent::AxisChanged(1, GamepadAxis::LeftStickY, 0.1));
+    backend.push(GamepadEvent::AxisChanged(1, GamepadAxis::RightStickX, 1.0));
+    gamepads.poll();
+
+    let pad = gamepads.get(1).unwrap();
+    assert_eq!(pad.left_stick(), (0.0, 0.0));
+    assert_eq!(pad.raw_axis(GamepadAxis::LeftStickX), 0.1);
+    assert_eq!(pad.right_stick(), (1.0, 0.0));
+}
@@ -0,0 +1,378 @@
+use wgpu_app::io::{
+    keyboard::{Keyboard, PhysicalKey},
+    mouse::Mouse,
+    touch::Touch,
+};
+use wgpu_app::testing::TestContext;
+use winit::dpi::PhysicalPosition;
+use winit::event::{
+    DeviceId, ElementState, Event, KeyboardInput, MouseButton, Touch as WinitTouch, TouchPhase,
+    VirtualKeyCode, WindowEvent,
+};
+use winit::window::WindowId;
+
+fn window_event(event: WindowEvent<'static>) -> Event<'static, ()> {
+    Event::WindowEvent {
+        // Safety: the ids are only compared, never passed back into winit
+        window_id: unsafe { WindowId::dummy() },
+        event,
+    }
+}
+
+fn key(key: VirtualKeyCode, state: ElementState) -> Event<'static, ()> {
+    scancode_key(0, Some(key), state)
+}
+
+#[allow(deprecated)]
+fn scancode_key(
+    scancode: u32,
+    key: Option<VirtualKeyCode>,
+    state: ElementState,
+) -> Event<'static, ()> {
+    window_event(WindowEvent::KeyboardInput {
+        device_id: unsafe { DeviceId::dummy() },
+        input: KeyboardInput {
+            scancode,
+            state,
+            virtual_keycode: key,
+            modifiers: Default::default(),
+        },
+        is_synthetic: false,
+    })
+}
+
+#[allow(deprecated)]
+fn button(button: MouseButton, state: ElementState) -> Event<'static, ()> {
+    window_event(WindowEvent::MouseInput {
+        device_id: unsafe { DeviceId::dummy() },
+        state,
+        button,
+        modifiers: Default::default(),
+    })
+}
+
+fn touch(id: u64, phase: TouchPhase, x: f64, y: f64) -> Event<'static, ()> {
+    window_event(WindowEvent::Touch(WinitTouch {
+        device_id: unsafe { DeviceId::dummy() },
+        phase,
+        location: PhysicalPosition::new(x, y),
+        force: None,
+        id,
+    }))
+}
+
+#[test]
+fn key_tapped_within_one_frame_reports_press_and_release() {
+    let mut keyboard = Keyboard::new();
+    keyboard.handle_event(&key(VirtualKeyCode::Space, ElementState::Pressed));
+    keyboard.handle_event(&key(VirtualKeyCode::Space, ElementState::Released));
+
+    assert!(keyboard.pressed_this_frame(&VirtualKeyCode::Space));
+    assert!(keyboard.released_this_frame(&VirtualKeyCode::Space));
+    assert!(!keyboard.is_pressed(&VirtualKeyCode::Space));
+
+    keyboard.next_frame();
+    assert!(!keyboard.pressed_this_frame(&VirtualKeyCode::Space));
+    assert!(!keyboard.released_this_frame(&VirtualKeyCode::Space));
+}
+
+#[test]
+fn key_tapped_twice_within_one_frame_is_counted_twice() {
+    let mut keyboard = Keyboard::new();
+    for _ in 0..2 {
+        keyboard.handle_event(&key(VirtualKeyCode::A, ElementState::Pressed));
+        keyboard.handle_event(&key(VirtualKeyCode::A, ElementState::Released));
+    }
+    keyboard.handle_event(&key(VirtualKeyCode::A, ElementState::Pressed));
+
+    assert_eq!(keyboard.press_count(&VirtualKeyCode::A), 3);
+    assert_eq!(keyboard.release_count(&VirtualKeyCode::A), 2);
+    assert!(keyboard.is_pressed(&VirtualKeyCode::A));
+}
+
+#[test]
+fn key_repeat_is_not_a_new_press() {
+    let mut keyboard = Keyboard::new();
+    keyboard.handle_event(&key(VirtualKeyCode::W, ElementState::Pressed));
+    keyboard.next_frame();
+    keyboard.handle_event(&key(VirtualKeyCode::W, ElementState::Pressed));
+
+    assert!(keyboard.is_pressed(&VirtualKeyCode::W));
+    assert!(!keyboard.pressed_this_frame(&VirtualKeyCode::W));
+}
+
+#[test]
+fn release_and_press_within_one_frame_keeps_key_held() {
+    let mut keyboard = Keyboard::new();
+    keyboard.handle_event(&key(VirtualKeyCode::W, ElementState::Pressed));
+    keyboard.next_frame();
+    keyboard.handle_event(&key(VirtualKeyCode::W, ElementState::Released));
+    keyboard.handle_event(&key(VirtualKeyCode::W, ElementState::Pressed));
+
+    assert!(keyboard.released_this_frame(&VirtualKeyCode::W));
+    assert!(keyboard.pressed_this_frame(&VirtualKeyCode::W));
+    assert!(keyboard.is_pressed(&VirtualKeyCode::W));
+}
+
+#[test]
+fn mouse_click_within_one_frame_reports_press_and_release() {
+    let mut mouse = Mouse::new();
+    mouse.handle_event(&button(MouseButton::Left, ElementState::Pressed));
+    mouse.handle_event(&button(MouseButton::Left, ElementState::Released));
+
+    assert!(mouse.pressed_this_frame(MouseButton::Left));
+    assert!(mouse.released_this_frame(MouseButton::Left));
+    assert!(!mouse.is_pressed(MouseButton::Left));
+    assert_eq!(mouse.press_count(MouseButton::Left), 1);
+
+    mouse.next_frame();
+    assert!(!mouse.pressed_this_frame(MouseButton::Left));
+    assert!(!mouse.released_this_frame(MouseButton::Left));
+}
+
+#[test]
+fn extra_mouse_buttons_are_distinct_from_the_main_buttons() {
+    let mut mouse = Mouse::new();
+    mouse.handle_event(&button(MouseButton::Other(0), ElementState::Pressed));
+    mouse.handle_event(&button(MouseButton::Other(15), ElementState::Pressed));
+
+    assert!(mouse.is_pressed(MouseButton::Other(0)));
+    assert!(mouse.is_pressed(MouseButton::Other(15)));
+    assert!(!mouse.is_pressed(MouseButton::Left));
+    assert!(!mouse.is_pressed(MouseButton::Other(200)));
+    assert_eq!(mouse.pressed_buttons().count(), 2);
+}
+
+#[test]
+fn injected_double_click_is_detected() {
+    let mut ctx = TestContext::new();
+    ctx.mouse.inject_move(100.0, 100.0);
+    ctx.mouse.inject_press(MouseButton::Left);
+    ctx.mouse.inject_release(MouseButton::Left);
+    ctx.advance(0.1);
+    assert_eq!(ctx.mouse.click_count(MouseButton::Left), 1);
+    ctx.next_frame();
+
+    ctx.mouse.inject_move(101.0, 100.0);
+    ctx.mouse.inject_press(MouseButton::Left);
+    ctx.advance(0.1);
+    assert!(ctx.mouse.double_clicked(MouseButton::Left));
+    assert_eq!(ctx.mouse.cursor_delta(), (1.0, 0.0));
+}
+
+#[test]
+fn injected_key_auto_repeats_after_delay() {
+    let mut ctx = TestContext::new();
+    ctx.keyboard.inject_press(VirtualKeyCode::Down);
+    ctx.advance(0.4);
+    assert_eq!(ctx.keyboard.repeat_count(&VirtualKeyCode::Down), 0);
+    ctx.next_frame();
+
+    ctx.advance(0.2);
+    assert_eq!(ctx.keyboard.repeat_count(&VirtualKeyCode::Down), 3);
+    assert!(ctx.keyboard.pressed_or_repeated(&VirtualKeyCode::Down));
+}
+
+#[test]
+fn editing_keys_are_text_input_and_control_characters_are_not() {
+    let mut keyboard = Keyboard::new();
+    keyboard.inject_text("ab");
+    keyboard.handle_event(&key(VirtualKeyCode::Back, ElementState::Pressed));
+    // OS key repeat
+    keyboard.handle_event(&key(VirtualKeyCode::Back, ElementState::Pressed));
+    // macOS sends DEL for backspace, which must not count as a second delete
+    keyboard.handle_event(&window_event(WindowEvent::ReceivedCharacter('\u{7f}')));
+    keyboard.inject_text("c\r");
+    keyboard.handle_event(&key(VirtualKeyCode::NumpadEnter, ElementState::Pressed));
+
+    assert_eq!(keyboard.backspace_count(), 2);
+    assert_eq!(keyboard.enter_count(), 1);
+    assert_eq!(keyboard.typed_text(), "abc");
+
+    let mut text = String::from("x");
+    assert!(keyboard.apply_text_input(&mut text));
+    assert_eq!(text, "xc");
+}
+
+#[test]
+fn primary_touch_is_only_assigned_when_no_other_finger_is_down() {
+    let mut touch = Touch::new();
+    touch.inject_touch(1, TouchPhase::Started, 0.0, 0.0);
+    touch.inject_touch(2, TouchPhase::Started, 10.0, 0.0);
+    touch.inject_touch(1, TouchPhase::Ended, 0.0, 0.0);
+    touch.next_frame();
+    touch.inject_touch(3, TouchPhase::Started, 20.0, 0.0);
+    assert_eq!(touch.primary(), None);
+
+    touch.inject_touch(2, TouchPhase::Ended, 10.0, 0.0);
+    touch.inject_touch(3, TouchPhase::Ended, 20.0, 0.0);
+    touch.next_frame();
+    touch.inject_touch(4, TouchPhase::Started, 30.0, 0.0);
+    assert_eq!(touch.primary().map(|point| point.id), Some(4));
+}
+
+#[test]
+fn primary_touch_emulates_the_left_mouse_button() {
+    let mut ctx = TestContext::new();
+    ctx.touch.emulate_mouse = true;
+    ctx.handle_event(&touch(1, TouchPhase::Started, 5.0, 5.0));
+    ctx.handle_event(&touch(2, TouchPhase::Started, 50.0, 50.0));
+    ctx.handle_event(&touch(1, TouchPhase::Moved, 8.0, 9.0));
+    ctx.advance(1.0 / 60.0);
+
+    assert!(ctx.mouse.pressed_this_frame(MouseButton::Left));
+    assert_eq!(ctx.mouse.get_pos(), (8.0, 9.0));
+    ctx.next_frame();
+
+    ctx.handle_event(&touch(2, TouchPhase::Ended, 50.0, 50.0));
+    assert!(ctx.mouse.is_pressed(MouseButton::Left));
+    ctx.handle_event(&touch(1, TouchPhase::Ended, 8.0, 9.0));
+    assert!(ctx.mouse.released_this_frame(MouseButton::Left));
+    assert!(!ctx.mouse.is_pressed(MouseButton::Left));
+}
+
+#[test]
+fn release_of_a_captured_press_is_dropped() {
+    let mut keyboard = Keyboard::new();
+    let mut mouse = Mouse::new();
+    keyboard.handle_captured_event(&key(VirtualKeyCode::A, ElementState::Pressed));
+    mouse.handle_captured_event(&button(MouseButton::Left, ElementState::Pressed));
+    keyboard.handle_event(&key(VirtualKeyCode::A, ElementState::Released));
+    mouse.handle_event(&button(MouseButton::Left, ElementState::Released));
+
+    assert!(!keyboard.released_this_frame(&VirtualKeyCode::A));
+    assert!(!mouse.released_this_frame(MouseButton::Left));
+
+    // The next press is not captured, so its release counts again
+    keyboard.handle_event(&key(VirtualKeyCode::A, ElementState::Pressed));
+    keyboard.handle_event(&key(VirtualKeyCode::A, ElementState::Released));
+    assert!(keyboard.released_this_frame(&VirtualKeyCode::A));
+}
+
+#[test]
+fn captured_repeat_of_a_held_key_keeps_its_release() {
+    let mut keyboard = Keyboard::new();
+    keyboard.handle_event(&key(VirtualKeyCode::W, ElementState::Pressed));
+    keyboard.handle_captured_event(&key(VirtualKeyCode::W, ElementState::Pressed));
+    keyboard.handle_event(&key(VirtualKeyCode::W, ElementState::Released));
+
+    assert!(!keyboard.is_pressed(&VirtualKeyCode::W));
+}
+
+#[test]
+fn physical_key_is_tracked_regardless_of_layout() {
+    let mut keyboard = Keyboard::new();
+    // On AZERTY the key in the W position produces Z
+    let w = PhysicalKey::W.scancode();
+    keyboard.handle_event(&scancode_key(
+        w,
+        Some(VirtualKeyCode::Z),
+        ElementState::Pressed,
+    ));
+
+    assert!(keyboard.is_physical_pressed(PhysicalKey::W));
+    assert!(keyboard.physical_pressed_this_frame(PhysicalKey::W));
+    assert!(keyboard.is_pressed(&VirtualKeyCode::Z));
+    assert!(!keyboard.is_pressed(&VirtualKeyCode::W));
+
+    keyboard.next_frame();
+    keyboard.handle_event(&scancode_key(
+        w,
+        Some(VirtualKeyCode::Z),
+        ElementState::Released,
+    ));
+    assert!(keyboard.physical_released_this_frame(PhysicalKey::W));
+    assert!(!keyboard.is_physical_pressed(PhysicalKey::W));
+}
+
+#[test]
+fn key_without_virtual_keycode_is_tracked_by_scancode() {
+    let mut keyboard = Keyboard::new();
+    keyboard.handle_event(&scancode_key(0x1234, None, ElementState::Pressed));
+
+    assert!(keyboard.is_scancode_pressed(0x1234));
+    assert!(keyboard.scancode_pressed_this_frame(0x1234));
+
+    keyboard.handle_event(&scancode_key(0x1234, None, ElementState::Released));
+    assert!(keyboard.scancode_released_this_frame(0x1234));
+    assert!(!keyboard.is_scancode_pressed(0x1234));
+}
+
+#[test]
+fn line_and_pixel_scrolling_are_combined() {
+    let mut mouse = Mouse::new();
+    mouse.set_lines_to_pixels(20.0);
+    mouse.inject_scroll(1.0, 2.0);
+    mouse.inject_pixel_scroll(10.0, -40.0);
+
+    assert_eq!(mouse.get_scroll(), (1.5, 0.0));
+    assert_eq!(mouse.get_scroll_pixels(), (30.0, 0.0));
+
+    mouse.next_frame();
+    assert_eq!(mouse.get_scroll(), (0.0, 0.0));
+    assert_eq!(mouse.get_scroll_pixels(), (0.0, 0.0));
+}
+
+#[test]
+fn logical_position_uses_the_scale_factor() {
+    let mut mouse = Mouse::new();
+    mouse.set_scale_factor(2.0);
+    mouse.inject_move(100.0, 50.0);
+
+    assert_eq!(mouse.scale_factor(), 2.0);
+    assert_eq!(mouse.get_pos(), (100.0, 50.0));
+    assert_eq!(mouse.get_logical_pos(), (50.0, 25.0));
+}
+
+#[test]
+fn quick_short_touch_is_a_tap() {
+    let mut touch = Touch::new();
+    touch.inject_touch(1, TouchPhase::Started, 0.0, 0.0);
+    touch.advance_time(0.1);
+    touch.inject_touch(1, TouchPhase::Ended, 3.0, 4.0);
+    assert_eq!(touch.taps(), &[(3.0, 4.0)]);
+
+    touch.next_frame();
+    assert!(touch.taps().is_empty());
+}
+
+#[test]
+fn long_or_moving_touch_is_not_a_tap() {
+    let mut touch = Touch::new();
+    touch.inject_touch(1, TouchPhase::Started, 0.0, 0.0);
+    touch.advance_time(0.5);
+    touch.inject_touch(1, TouchPhase::Ended, 0.0, 0.0);
+
+    touch.inject_touch(2, TouchPhase::Started, 0.0, 0.0);
+    touch.inject_touch(2, TouchPhase::Moved, 20.0, 0.0);
+    touch.inject_touch(2, TouchPhase::Ended, 20.0, 0.0);
+
+    assert!(touch.taps().is_empty());
+}
+
+#[test]
+fn two_finger_pinch_and_pan() {
+    let mut touch = Touch::new();
+    touch.inject_touch(1, TouchPhase::Started, 0.0, 0.0);
+    touch.inject_touch(2, TouchPhase::Started, 100.0, 0.0);
+    touch.next_frame();
+
+    touch.inject_touch(2, TouchPhase::Moved, 200.0, 0.0);
+    assert_eq!(touch.pinch_scale(), 2.0);
+    assert_eq!(touch.pan_delta(), (50.0, 0.0));
+    assert_eq!(touch.rotation(), 0.0);
+}
+
+#[test]
+fn rotation_wraps_around_half_a_turn() {
+    let mut touch = Touch::new();
+    // The second finger is left of the first, so the angle between them is close to -pi
+    touch.inject_touch(1, TouchPhase::Started, 0.0, 0.0);
+    touch.inject_touch(2, TouchPhase::Started, -100.0, 1.0);
+    touch.next_frame();
+
+    // Moving it up on screen crosses over to +pi, which is a small clockwise turn
+    touch.inject_touch(2, TouchPhase::Moved, -100.0, -1.0);
+    let expected = -2.0 * 0.01f64.atan();
+    assert!((touch.rotation() - expected).abs() < 1e-9);
+}
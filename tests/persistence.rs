@@ -0,0 +1,80 @@
+#![cfg(feature = "persistence")]
+
+use wgpu_app::persistence::{Storage, WindowGeometry};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+#[test]
+fn values_and_memory_survive_a_save() {
+    let path = std::env::temp_dir()
+        .join(format!("wgpu_app_persistence_{}", std::process::id()))
+        .join("gui.ron");
+    let id = egui::Id::new("collapsed");
+
+    let (mut storage, memory) = Storage::load(Some(path.clone()));
+    assert!(memory.is_none());
+    storage.set("volume", &0.5f32);
+    storage.set("recent", &vec!["a.txt".to_string()]);
+    let ctx = egui::Context::default();
+    ctx.data_mut(|data| data.insert_persisted(id, true));
+    storage.save(&ctx).unwrap();
+
+    let (storage, memory) = Storage::load(Some(path.clone()));
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    assert_eq!(storage.get::<f32>("volume"), Some(0.5));
+    assert_eq!(
+        storage.get::<Vec<String>>("recent"),
+        Some(vec!["a.txt".to_string()])
+    );
+    assert_eq!(storage.get::<f32>("missing"), None);
+    let mut memory = memory.unwrap();
+    assert_eq!(memory.data.get_persisted::<bool>(id), Some(true));
+}
+
+#[test]
+fn window_geometry_is_kept_on_screen() {
+    let monitors = [(PhysicalPosition::new(0, 0), PhysicalSize::new(1920, 1080))];
+    let geometry = WindowGeometry {
+        position: Some((100, 100)),
+        size: (800, 600),
+        maximized: false,
+        fullscreen: false,
+    };
+    assert_eq!(geometry.sanitized(&monitors), geometry);
+
+    // Saved on a second monitor that is no longer connected
+    let off_screen = WindowGeometry {
+        position: Some((2500, 100)),
+        size: (2560, 1440),
+        ..geometry.clone()
+    };
+    let sanitized = off_screen.sanitized(&monitors);
+    assert_eq!(sanitized.position, None);
+    assert_eq!(sanitized.size, (1920, 1080));
+
+    // Mostly off the left edge, but the right of the title bar is still reachable
+    let partly_visible = WindowGeometry {
+        position: Some((-700, 0)),
+        ..geometry
+    };
+    assert_eq!(
+        partly_visible.sanitized(&monitors).position,
+        Some((-700, 0))
+    );
+}
+
+#[test]
+fn tiny_saved_size_is_grown_to_the_minimum() {
+    // What a window minimized on Windows used to be saved as
+    let minimized = WindowGeometry {
+        position: Some((-32000, -32000)),
+        size: (0, 0),
+        maximized: false,
+        fullscreen: false,
+    };
+    let monitors = [(PhysicalPosition::new(0, 0), PhysicalSize::new(1920, 1080))];
+
+    let sanitized = minimized.sanitized(&monitors);
+    assert_eq!(sanitized.size, WindowGeometry::MIN_SIZE);
+    assert_eq!(sanitized.position, None);
+    assert_eq!(minimized.sanitized(&[]).size, WindowGeometry::MIN_SIZE);
+}
@@ -0,0 +1,33 @@
+use egui::CursorIcon;
+use wgpu_app::platform_output::{PlatformOutputFilter, RecordingHandler};
+
+#[test]
+fn recording_handler_intercepts_platform_output() {
+    let ctx = egui::Context::default();
+    let recording = RecordingHandler::new();
+    let mut filter = PlatformOutputFilter::new(Box::new(recording.clone()));
+
+    let output = ctx.run(Default::default(), |ctx| {
+        ctx.output_mut(|output| {
+            output.copied_text = "copied".to_string();
+            output.open_url("https://example.com");
+        });
+        ctx.set_cursor_icon(CursorIcon::Text);
+    });
+    let remaining = filter.filter(output.platform_output);
+
+    assert_eq!(recording.copied_text(), ["copied"]);
+    assert_eq!(recording.opened_urls(), ["https://example.com"]);
+    assert_eq!(recording.cursor_icons(), [CursorIcon::Text]);
+    assert!(remaining.copied_text.is_empty());
+    assert!(remaining.open_url.is_none());
+    assert_eq!(remaining.cursor_icon, CursorIcon::Default);
+
+    // Unchanged cursor icons aren't reported again
+    recording.clear();
+    let output = ctx.run(Default::default(), |ctx| {
+        ctx.set_cursor_icon(CursorIcon::Text)
+    });
+    filter.filter(output.platform_output);
+    assert!(recording.cursor_icons().is_empty());
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "record")]
+
+use wgpu_app::io::{keyboard::Keyboard, mouse::Mouse};
+use wgpu_app::record::{InputRecorder, InputReplay, RecordedEvent};
+use wgpu_app::Timer;
+use winit::event::{Ime, MouseButton, TouchPhase, VirtualKeyCode};
+
+#[test]
+fn replay_reproduces_recorded_input_and_timing() {
+    let path = std::env::temp_dir().join(format!("wgpu_app_record_{}.jsonl", std::process::id()));
+
+    let mut recorder = InputRecorder::create(&path).unwrap();
+    let press = RecordedEvent::Key {
+        scancode: 17,
+        key: Some(VirtualKeyCode::W),
+        pressed: true,
+    };
+    recorder.record_event(&press.to_event()).unwrap();
+    recorder
+        .record_event(&RecordedEvent::CursorMoved { x: 10.5, y: 20.0 }.to_event())
+        .unwrap();
+    recorder.record_frame(0.25).unwrap();
+    recorder
+        .record_event(
+            &RecordedEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed: true,
+            }
+            .to_event(),
+        )
+        .unwrap();
+    recorder.record_frame(0.5).unwrap();
+    recorder.flush().unwrap();
+    drop(recorder);
+
+    let mut replay = InputReplay::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let (mut keyboard, mut mouse, mut timer) = (Keyboard::new(), Mouse::new(), Timer::new());
+
+    let frame = replay.next_frame().unwrap();
+    frame.apply(&mut keyboard, &mut mouse, &mut timer);
+    assert!(keyboard.pressed_this_frame(&VirtualKeyCode::W));
+    assert_eq!(mouse.get_pos(), (10.5, 20.0));
+    assert_eq!(timer.delta(), 0.25);
+    keyboard.next_frame();
+    mouse.next_frame();
+
+    let frame = replay.next_frame().unwrap();
+    frame.apply(&mut keyboard, &mut mouse, &mut timer);
+    assert!(!keyboard.pressed_this_frame(&VirtualKeyCode::W));
+    assert_eq!(keyboard.held_duration(&VirtualKeyCode::W), 0.75);
+    assert!(mouse.pressed_this_frame(MouseButton::Left));
+    assert_eq!(timer.delta(), 0.5);
+
+    assert!(replay.next_frame().is_none());
+}
+
+#[test]
+fn ime_touch_and_scale_factor_survive_a_recording() {
+    let path =
+        std::env::temp_dir().join(format!("wgpu_app_record_ime_{}.jsonl", std::process::id()));
+    let touch = RecordedEvent::Touch {
+        id: 3,
+        phase: TouchPhase::Started,
+        x: 4.0,
+        y: 5.0,
+        force: Some(0.5),
+    };
+
+    let mut recorder = InputRecorder::create(&path).unwrap();
+    for event in [
+        RecordedEvent::ScaleFactorChanged {
+            scale_factor: 2.0,
+            width: 800,
+            height: 600,
+        },
+        RecordedEvent::Ime(Ime::Commit("日本".to_string())),
+        touch.clone(),
+    ] {
+        recorder.record_event(&event.to_event()).unwrap();
+    }
+    recorder.record_frame(0.1).unwrap();
+    drop(recorder);
+
+    let mut replay = InputReplay::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let (mut keyboard, mut mouse, mut timer) = (Keyboard::new(), Mouse::new(), Timer::new());
+
+    let frame = replay.next_frame().unwrap();
+    frame.apply(&mut keyboard, &mut mouse, &mut timer);
+    assert_eq!(keyboard.typed_text(), "日本");
+    assert_eq!(mouse.scale_factor(), 2.0);
+    assert_eq!(RecordedEvent::from_event(&frame.events[2]), Some(touch));
+}
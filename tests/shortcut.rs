@@ -0,0 +1,82 @@
+use wgpu_app::io::keyboard::{ParseShortcutError, Shortcut};
+use winit::event::{ModifiersState, VirtualKeyCode};
+
+fn parse(s: &str) -> Result<Shortcut, ParseShortcutError> {
+    s.parse()
+}
+
+#[test]
+fn plus_and_minus_keys() {
+    assert_eq!(
+        parse("Ctrl++"),
+        Ok(Shortcut::new(ModifiersState::CTRL, VirtualKeyCode::Plus))
+    );
+    assert_eq!(
+        parse("+"),
+        Ok(Shortcut::new(ModifiersState::empty(), VirtualKeyCode::Plus))
+    );
+    assert_eq!(
+        parse("Ctrl+-"),
+        Ok(Shortcut::new(ModifiersState::CTRL, VirtualKeyCode::Minus))
+    );
+}
+
+#[test]
+fn invalid_shortcuts() {
+    assert_eq!(parse("A+B"), Err(ParseShortcutError::MultipleKeys));
+    assert_eq!(parse("Ctrl+"), Err(ParseShortcutError::MissingKey));
+    assert_eq!(parse(""), Err(ParseShortcutError::MissingKey));
+    assert_eq!(
+        parse("Ctrl+Nope"),
+        Err(ParseShortcutError::UnknownKey("Nope".to_string()))
+    );
+}
+
+#[test]
+fn names_are_case_insensitive() {
+    let expected = Shortcut::new(
+        ModifiersState::CTRL | ModifiersState::SHIFT,
+        VirtualKeyCode::F5,
+    );
+    assert_eq!(parse("ctrl+shift+f5"), Ok(expected));
+    assert_eq!(parse("CTRL+SHIFT+F5"), Ok(expected));
+    assert_eq!(parse(" Shift + Ctrl + F5 "), Ok(expected));
+    assert_eq!(
+        parse("capital"),
+        Ok(Shortcut::new(
+            ModifiersState::empty(),
+            VirtualKeyCode::Capital
+        ))
+    );
+}
+
+#[test]
+fn display_round_trips() {
+    let keys = [
+        VirtualKeyCode::A,
+        VirtualKeyCode::Key1,
+        VirtualKeyCode::F24,
+        VirtualKeyCode::Plus,
+        VirtualKeyCode::Minus,
+        VirtualKeyCode::LControl,
+        VirtualKeyCode::Numlock,
+        VirtualKeyCode::NumpadDecimal,
+        VirtualKeyCode::NumpadEquals,
+        VirtualKeyCode::Apps,
+        VirtualKeyCode::Asterisk,
+        VirtualKeyCode::Colon,
+        VirtualKeyCode::PlayPause,
+        VirtualKeyCode::OEM102,
+        VirtualKeyCode::Cut,
+    ];
+    for key in keys {
+        for modifiers in [
+            ModifiersState::empty(),
+            ModifiersState::CTRL | ModifiersState::ALT,
+            ModifiersState::all(),
+        ] {
+            let shortcut = Shortcut::new(modifiers, key);
+            assert_eq!(parse(&shortcut.to_string()), Ok(shortcut));
+        }
+    }
+}
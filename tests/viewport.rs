@@ -0,0 +1,74 @@
+use wgpu_app::utils::viewport::OrbitCamera;
+
+/// Transforms a point with a column major matrix and returns normalized device coordinates
+fn project(m: [[f32; 4]; 4], p: [f32; 3]) -> [f32; 3] {
+    let clip: Vec<f32> = (0..4)
+        .map(|r| m[0][r] * p[0] + m[1][r] * p[1] + m[2][r] * p[2] + m[3][r])
+        .collect();
+    [clip[0] / clip[3], clip[1] / clip[3], clip[2] / clip[3]]
+}
+
+fn assert_near(a: f32, b: f32) {
+    assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+}
+
+fn camera() -> OrbitCamera {
+    OrbitCamera {
+        target: [1.0, 2.0, -3.0],
+        yaw: 0.7,
+        pitch: -0.4,
+        ..OrbitCamera::default()
+    }
+}
+
+#[test]
+fn target_is_in_the_center_of_the_screen() {
+    let camera = camera();
+    let ndc = project(camera.view_projection(1.5), camera.target);
+    assert_near(ndc[0], 0.0);
+    assert_near(ndc[1], 0.0);
+}
+
+#[test]
+fn near_and_far_map_to_depth_zero_and_one() {
+    let camera = camera();
+    let eye = camera.eye();
+    let point_at = |distance: f32| {
+        let dir: Vec<f32> = (0..3)
+            .map(|i| (camera.target[i] - eye[i]) / camera.distance)
+            .collect();
+        [
+            eye[0] + dir[0] * distance,
+            eye[1] + dir[1] * distance,
+            eye[2] + dir[2] * distance,
+        ]
+    };
+
+    let m = camera.view_projection(1.0);
+    assert_near(project(m, point_at(camera.near))[2], 0.0);
+    assert_near(project(m, point_at(camera.far))[2], 1.0);
+    let depth = project(m, camera.target)[2];
+    assert!(depth > 0.0 && depth < 1.0);
+}
+
+#[test]
+fn pitch_is_clamped_short_of_straight_up_and_down() {
+    let mut camera = OrbitCamera::default();
+    camera.orbit(0.0, 1e6);
+    assert!(camera.pitch < std::f32::consts::FRAC_PI_2);
+    assert!(camera.pitch > 1.5);
+
+    camera.orbit(0.0, -1e6);
+    assert!(camera.pitch > -std::f32::consts::FRAC_PI_2);
+    assert!(camera.pitch < -1.5);
+}
+
+#[test]
+fn zoom_stops_at_the_near_plane() {
+    let mut camera = OrbitCamera::default();
+    camera.zoom(1000.0);
+    assert_eq!(camera.distance, camera.near);
+
+    camera.zoom(-2.0);
+    assert!(camera.distance > camera.near);
+}